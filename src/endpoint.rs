@@ -12,4 +12,27 @@ impl Endpoint {
     // REAL-TIME EVENTS
     pub const REGISTER_EVENT_QUEUE: &'static str = "register";
     pub const EVENTS_QUEUE: &'static str = "events";
+
+    // MESSAGES
+    pub const MESSAGES: &'static str = "messages";
+    pub const RENDER_MESSAGE: &'static str = "messages/render";
+    pub const MESSAGE_FLAGS: &'static str = "messages/flags";
+    pub const MESSAGE_FLAGS_NARROW: &'static str = "messages/flags/narrow";
+    pub const MARK_ALL_AS_READ: &'static str = "mark_all_as_read";
+    pub const MARK_STREAM_AS_READ: &'static str = "mark_stream_as_read";
+    pub const MARK_TOPIC_AS_READ: &'static str = "mark_topic_as_read";
+    pub const SCHEDULED_MESSAGES: &'static str = "scheduled_messages";
+    pub const DRAFTS: &'static str = "drafts";
+
+    // STREAMS
+    pub const STREAMS: &'static str = "streams";
+    pub const GET_STREAM_ID: &'static str = "get_stream_id";
+    pub const SUBSCRIPTIONS: &'static str = "users/me/subscriptions";
+    pub const SUBSCRIPTION_PROPERTIES: &'static str = "users/me/subscriptions/properties";
+    pub const USER_TOPICS: &'static str = "user_topics";
+
+    // USERS
+    pub const USERS: &'static str = "users";
+    pub const OWN_USER: &'static str = "users/me";
+    pub const SETTINGS: &'static str = "settings";
 }
@@ -1,3 +1,5 @@
+use crate::message::UnsupportedDestination;
+use crate::narrow::NarrowError;
 use reqwest::Error as HttpError;
 use serde::Deserialize;
 use std::error::Error as StdError;
@@ -9,10 +11,10 @@ pub struct Error {
 }
 
 impl Error {
-    fn new_builder(http_error: HttpError) -> Self {
+    fn new_builder<E: StdError + Send + Sync + 'static>(build_error: E) -> Self {
         Self {
             kind: ErrorKind::Build,
-            source: Some(Box::new(http_error)),
+            source: Some(Box::new(build_error)),
         }
     }
 
@@ -23,6 +25,20 @@ impl Error {
         }
     }
 
+    pub(crate) fn new_decode(decode_error: serde_json::Error) -> Self {
+        Self {
+            kind: ErrorKind::Decode,
+            source: Some(Box::new(decode_error)),
+        }
+    }
+
+    pub(crate) fn new_handler(handler_error: Box<dyn StdError + Send + Sync>) -> Self {
+        Self {
+            kind: ErrorKind::Handler,
+            source: Some(handler_error),
+        }
+    }
+
     pub(crate) fn new_zulip(zulip_error: ZulipError) -> Self {
         Self {
             kind: ErrorKind::Zulip(zulip_error),
@@ -54,6 +70,14 @@ impl Error {
             _ => false,
         }
     }
+
+    pub fn is_decode(&self) -> bool {
+        matches!(self.kind, ErrorKind::Decode)
+    }
+
+    pub fn is_handler(&self) -> bool {
+        matches!(self.kind, ErrorKind::Handler)
+    }
 }
 
 impl fmt::Display for Error {
@@ -65,6 +89,18 @@ impl fmt::Display for Error {
                     write!(f, ": {}", source)?;
                 }
             }
+            ErrorKind::Decode => {
+                f.write_str("decode error")?;
+                if let Some(ref source) = self.source {
+                    write!(f, ": {}", source)?;
+                }
+            }
+            ErrorKind::Handler => {
+                f.write_str("event handler error")?;
+                if let Some(ref source) = self.source {
+                    write!(f, ": {}", source)?;
+                }
+            }
             ErrorKind::Zulip(ref zulip) => write!(f, "zulip error: {}", zulip.message)?,
             ErrorKind::Http(ref http_e) => write!(f, "http client error: {}", http_e)?,
         }
@@ -103,11 +139,26 @@ impl From<HttpError> for Error {
     }
 }
 
+impl From<NarrowError> for Error {
+    fn from(narrow_error: NarrowError) -> Self {
+        Error::new_builder(narrow_error)
+    }
+}
+
+impl From<UnsupportedDestination> for Error {
+    fn from(destination_error: UnsupportedDestination) -> Self {
+        Error::new_builder(destination_error)
+    }
+}
+
 #[derive(Debug)]
 pub enum ErrorKind {
     Zulip(ZulipError),
     Build,
     Http(HttpError),
+    /// The response of the server does not match the expected data.
+    Decode,
+    Handler,
 }
 
 #[derive(Deserialize, Debug)]
@@ -171,6 +222,24 @@ impl ZulipError {
             _ => false,
         }
     }
+
+    pub fn is_stream_not_found(&self) -> bool {
+        matches!(
+            self.code,
+            Some(ZulipErrorCode::StreamDoesNotExist { stream: _ })
+                | Some(ZulipErrorCode::StreamWithIdDoesNotExist { stream_id: _ })
+        )
+    }
+
+    /// Whether the user is not allowed to send a message with a wildcard
+    /// mention, e.g. `@**all**`, to that many users.
+    pub fn is_wildcard_mention_not_allowed(&self) -> bool {
+        matches!(
+            self.code,
+            Some(ZulipErrorCode::StreamWildcardMentionNotAllowed)
+                | Some(ZulipErrorCode::TopicWildcardMentionNotAllowed)
+        )
+    }
 }
 
 impl fmt::Display for ZulipError {
@@ -196,6 +265,16 @@ impl fmt::Display for ZulipError {
                 ZulipErrorCode::BadEventQueueId { queue_id } => {
                     write!(f, "bad event queue id: {}", queue_id)?
                 }
+                ZulipErrorCode::StreamDoesNotExist { stream } => {
+                    write!(f, "stream '{}' does not exist", stream)?
+                }
+                ZulipErrorCode::StreamWithIdDoesNotExist { stream_id } => {
+                    write!(f, "stream with id {} does not exist", stream_id)?
+                }
+                ZulipErrorCode::StreamWildcardMentionNotAllowed
+                | ZulipErrorCode::TopicWildcardMentionNotAllowed => {
+                    write!(f, "wildcard mention not allowed: {}", self.message)?
+                }
             }
         } else {
             f.write_str(self.message.as_str())?;
@@ -225,6 +304,14 @@ pub enum ZulipErrorCode {
     AuthenticationFailed,
     #[serde(rename = "BAD_EVENT_QUEUE_ID")]
     BadEventQueueId { queue_id: String },
+    #[serde(rename = "STREAM_DOES_NOT_EXIST")]
+    StreamDoesNotExist { stream: String },
+    #[serde(rename = "STREAM_WITH_ID_DOES_NOT_EXIST")]
+    StreamWithIdDoesNotExist { stream_id: u64 },
+    #[serde(rename = "STREAM_WILDCARD_MENTION_NOT_ALLOWED")]
+    StreamWildcardMentionNotAllowed,
+    #[serde(rename = "TOPIC_WILDCARD_MENTION_NOT_ALLOWED")]
+    TopicWildcardMentionNotAllowed,
 }
 
 #[cfg(test)]
@@ -299,6 +386,19 @@ mod tests {
                 "bad_event_queue",
             ))
             .await;
+        server
+            .register(mock(
+                ResponseTemplate::new(400).set_body_json(MockErrorResponse::stream_not_found()),
+                "stream_not_found",
+            ))
+            .await;
+        server
+            .register(mock(
+                ResponseTemplate::new(400)
+                    .set_body_json(MockErrorResponse::wildcard_mention_not_allowed()),
+                "wildcard_mention",
+            ))
+            .await;
 
         let httpc = HttpClient::new();
         let mut res = send_request(&httpc, server.uri(), "rate_limit").await?;
@@ -315,6 +415,11 @@ mod tests {
         assert!(res.is_auth_failed());
         res = send_request(&httpc, server.uri(), "bad_event_queue").await?;
         assert!(res.is_bad_event_queue());
+        res = send_request(&httpc, server.uri(), "stream_not_found").await?;
+        assert!(res.is_stream_not_found());
+        assert_eq!(res.to_string(), "stream 'Nowhere' does not exist");
+        res = send_request(&httpc, server.uri(), "wildcard_mention").await?;
+        assert!(res.is_wildcard_mention_not_allowed());
         res = send_request(&httpc, server.uri(), "no_code").await?;
         assert_eq!(res.code, None);
         Ok(())
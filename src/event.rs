@@ -1,24 +1,78 @@
-use crate::{Client, Endpoint, Error};
+mod checkpoint;
+mod kind;
+mod multiplex;
+mod register;
+mod router;
+mod stream;
+
+pub use checkpoint::{
+    CheckpointStore, FileCheckpointStore, MemoryCheckpointStore, QueueCheckpoint, StoreFuture,
+};
+pub use kind::*;
+pub use multiplex::{QueueSet, QueueSetStream};
+pub use register::*;
+pub use router::{ErrorPolicy, Router};
+pub use stream::EventStream;
+
+use crate::error::ErrorKind;
+use crate::narrow::Narrow;
+use crate::{Client, EmptyResponse, Endpoint, Error};
 use reqwest::Method;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of events buffered by an [`EventStream`] by default.
+const STREAM_CAPACITY: usize = 64;
+
+/// Time given to the server to answer a long-poll after its own timeout,
+/// before considering the connection stalled.
+const STALL_MARGIN: Duration = Duration::from_secs(15);
 
 pub struct Queue {
     dispatcher: Dispatcher,
 }
 
 impl Queue {
-    fn new(client: Client, response: RegisterQueueResponse) -> Self {
+    fn new(
+        client: Client,
+        request: RegisterQueueRequest,
+        response: RegisterQueueResponse,
+        options: QueueOptions,
+    ) -> Self {
         let dispatcher = Dispatcher {
             params: DispatcherParams {
-                queue_id: response.queue_id,
+                queue_id: response.queue_id.clone(),
                 last_event_id: response.last_event_id,
             },
+            registration: Arc::new(response),
+            request,
+            options,
+            resumed: false,
             client,
         };
 
         Queue { dispatcher }
     }
 
+    pub(crate) fn resume(client: Client, checkpoint: QueueCheckpoint) -> Self {
+        let response = RegisterQueueResponse {
+            queue_id: checkpoint.queue_id,
+            zulip_version: checkpoint.zulip_version,
+            zulip_feature_level: checkpoint.zulip_feature_level,
+            zulip_merge_base: None,
+            event_queue_longpoll_timeout_seconds: checkpoint.event_queue_longpoll_timeout_seconds,
+            last_event_id: checkpoint.last_event_id,
+            state: InitialState::default(),
+        };
+
+        let request = checkpoint.request.into();
+        let mut queue = Self::new(client, request, response, checkpoint.options);
+        queue.dispatcher.resumed = true;
+        queue
+    }
+
     pub fn id(&self) -> &str {
         self.dispatcher.queue_id()
     }
@@ -27,10 +81,68 @@ impl Queue {
         self.dispatcher.last_event_id()
     }
 
+    pub fn zulip_version(&self) -> &str {
+        self.dispatcher.registration.zulip_version()
+    }
+
+    pub fn zulip_feature_level(&self) -> u16 {
+        self.dispatcher.registration.zulip_feature_level()
+    }
+
+    pub fn zulip_merge_base(&self) -> Option<&str> {
+        self.dispatcher.registration.zulip_merge_base()
+    }
+
+    /// State of the realm when the queue was registered, or registered again
+    /// if it is [`resilient`](QueueBuilder::resilient). Empty for queues
+    /// resumed from a checkpoint until they are registered again.
+    pub fn initial_state(&self) -> &InitialState {
+        self.dispatcher.registration.initial_state()
+    }
+
+    /// Waits for new events, blocking until the server sends some.
     pub async fn events(&mut self) -> Result<Vec<Event>, Error> {
         self.dispatcher.events().await
     }
 
+    /// Returns the pending events without waiting, which may be none.
+    pub async fn try_events(&mut self) -> Result<Vec<Event>, Error> {
+        self.dispatcher.try_events().await
+    }
+
+    /// Waits for new events up to `timeout`, returning none if it elapses.
+    pub async fn events_timeout(&mut self, timeout: Duration) -> Result<Vec<Event>, Error> {
+        // Events are acknowledged on the next poll, so they are not lost if the
+        // current one is aborted.
+        match tokio::time::timeout(timeout, self.dispatcher.events()).await {
+            Ok(res) => res,
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Converts the queue into a [`Stream`](futures_core::Stream) that yields
+    /// events one by one, buffering up to 64 events.
+    ///
+    /// Must be called within a Tokio runtime, since events are polled by a
+    /// background task.
+    pub fn into_stream(self) -> EventStream {
+        self.into_stream_with_capacity(STREAM_CAPACITY)
+    }
+
+    /// Like [`Queue::into_stream`], but buffering up to `capacity` events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn into_stream_with_capacity(self, capacity: usize) -> EventStream {
+        EventStream::new(self.dispatcher, capacity)
+    }
+
+    /// Returns the current progress of the queue, to resume it later.
+    pub fn checkpoint(&self) -> QueueCheckpoint {
+        QueueCheckpoint::new(self)
+    }
+
     pub async fn unregister(self) -> Result<(), Error> {
         self.dispatcher.unregister().await?;
         Ok(())
@@ -39,6 +151,7 @@ impl Queue {
 
 pub struct QueueBuilder {
     request: RegisterQueueRequest,
+    options: QueueOptions,
     client: Client,
 }
 
@@ -46,10 +159,39 @@ impl QueueBuilder {
     pub(crate) fn new(client: Client) -> Self {
         Self {
             request: RegisterQueueRequest::default(),
+            options: QueueOptions::default(),
             client,
         }
     }
 
+    /// Registers the queue again with the same parameters when the server
+    /// garbage-collects it, instead of failing with `BAD_EVENT_QUEUE_ID`.
+    ///
+    /// Events sent while the queue was gone are lost, so an
+    /// [`EventKind::Resynchronized`] event carrying the new registration is
+    /// emitted to let consumers reload their state.
+    pub fn resilient(mut self, value: bool) -> Self {
+        self.options.resilient = value;
+        self
+    }
+
+    /// Returns the heartbeats sent by the server when there are no events,
+    /// to monitor the liveness of the queue. They are skipped by default.
+    pub fn heartbeats(mut self, value: bool) -> Self {
+        self.options.heartbeats = value;
+        self
+    }
+
+    /// Time to wait for the server to answer a long-poll before considering
+    /// the connection stalled, aborting the request and polling again.
+    ///
+    /// Since the server answers with a heartbeat when there are no events,
+    /// it defaults to the long-poll timeout of the server plus 15 seconds.
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.options.stall_timeout.replace(timeout);
+        self
+    }
+
     pub fn apply_markdown(mut self, value: bool) -> Self {
         self.request.apply_markdown.replace(value);
         self
@@ -88,38 +230,76 @@ impl QueueBuilder {
         self
     }
 
-    pub fn narrow<C, V>(mut self, condition: C, value: V) -> Self
+    /// Requests the initial state of the given event types, independently of
+    /// the events the queue is subscribed to. Defaults to the events set with
+    /// [`QueueBuilder::for_event`].
+    pub fn fetch_event_types<I, T>(mut self, events: I) -> Self
     where
-        C: Into<String>,
-        V: Into<String>,
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
     {
-        let condition = condition.into();
-        let value = value.into();
-
-        let events = self.request.narrow.get_or_insert(Vec::with_capacity(1));
-        if events
-            .iter()
-            .find(|[c, v]| c == &condition && v == &value)
-            .is_none()
-        {
-            events.push([condition, value]);
+        let fetch_events = self.request.fetch_event_types.get_or_insert(Vec::new());
+        for event in events.into_iter().map(Into::into) {
+            if !fetch_events.contains(&event) {
+                fetch_events.push(event);
+            }
         }
         self
     }
 
-    pub async fn register(self) -> Result<Queue, Error> {
-        let response: RegisterQueueResponse = self
-            .client
-            .send(Method::POST, Endpoint::REGISTER_EVENT_QUEUE, &self.request)
-            .await?;
+    /// Only receives the events of the messages matching the narrow. Filters
+    /// are added to the ones of previous calls.
+    ///
+    /// Event queues only support filters by stream, topic, sender and flags;
+    /// [`register`](Self::register) fails with other filters.
+    pub fn narrow<N: Into<Narrow>>(mut self, narrow: N) -> Self {
+        let filters = narrow.into().filters().to_vec();
+        self.request
+            .narrow
+            .get_or_insert_with(Narrow::new)
+            .extend(filters);
+        self
+    }
 
-        Ok(Queue::new(self.client, response))
+    pub async fn register(self) -> Result<Queue, Error> {
+        if let Some(ref narrow) = self.request.narrow {
+            narrow.validate_for_events()?;
+        }
+        let response = register(&self.client, &self.request).await?;
+
+        Ok(Queue::new(
+            self.client,
+            self.request,
+            response,
+            self.options,
+        ))
     }
 }
 
+async fn register(
+    client: &Client,
+    request: &RegisterQueueRequest,
+) -> Result<RegisterQueueResponse, Error> {
+    client
+        .send(Method::POST, Endpoint::REGISTER_EVENT_QUEUE, request)
+        .await
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct QueueOptions {
+    resilient: bool,
+    heartbeats: bool,
+    stall_timeout: Option<Duration>,
+}
+
 #[derive(Clone)]
 struct Dispatcher {
     params: DispatcherParams,
+    registration: Arc<RegisterQueueResponse>,
+    request: RegisterQueueRequest,
+    options: QueueOptions,
+    /// Whether the queue was resumed from a checkpoint and not polled yet
+    resumed: bool,
     client: Client,
 }
 
@@ -132,18 +312,59 @@ impl Dispatcher {
         self.params.last_event_id
     }
 
-    async fn fetch_events(&self) -> Result<EventsResponse, Error> {
-        self.client
-            .send(Method::GET, Endpoint::EVENTS_QUEUE, &self.params)
-            .await
+    fn stall_timeout(&self) -> Duration {
+        self.options
+            .stall_timeout
+            .unwrap_or_else(|| self.registration.event_queue_longpoll_timeout() + STALL_MARGIN)
+    }
+
+    async fn fetch_events(&self, dont_block: bool) -> Result<EventsResponse, Error> {
+        let params = EventsRequest {
+            queue_id: self.queue_id(),
+            last_event_id: self.last_event_id(),
+            dont_block: Some(dont_block).filter(|v| *v),
+        };
+
+        loop {
+            let request = self
+                .client
+                .send(Method::GET, Endpoint::EVENTS_QUEUE, &params);
+
+            // Dropping the request on timeout closes its connection, so a new
+            // one is opened to poll again.
+            match tokio::time::timeout(self.stall_timeout(), request).await {
+                Ok(res) => break res,
+                Err(_) => log::warn!(
+                    "long-poll of queue '{}' stalled, polling again",
+                    self.queue_id()
+                ),
+            }
+        }
     }
 
     async fn events(&mut self) -> Result<Vec<Event>, Error> {
+        self.poll(false).await
+    }
+
+    async fn try_events(&mut self) -> Result<Vec<Event>, Error> {
+        self.poll(true).await
+    }
+
+    async fn poll(&mut self, dont_block: bool) -> Result<Vec<Event>, Error> {
         loop {
-            let events = self.fetch_events().await?.events;
+            let events = match self.fetch_events(dont_block).await {
+                Ok(res) => res.events,
+                // A resumed queue may have expired while the checkpoint was stored
+                Err(e) if (self.options.resilient || self.resumed) && is_bad_event_queue(&e) => {
+                    let response = self.reregister().await?;
+                    break Ok(vec![Event::resynchronized(response)]);
+                }
+                Err(e) => break Err(e),
+            };
+            self.resumed = false;
             if let Some(evt) = events.iter().last() {
                 self.params.last_event_id = evt.id();
-                if evt.kind.as_str() == "heartbeat" {
+                if evt.is_heartbeat() && !self.options.heartbeats {
                     continue;
                 }
             }
@@ -151,6 +372,16 @@ impl Dispatcher {
         }
     }
 
+    async fn reregister(&mut self) -> Result<Arc<RegisterQueueResponse>, Error> {
+        let response = register(&self.client, &self.request).await?;
+        self.params = DispatcherParams {
+            queue_id: response.queue_id.clone(),
+            last_event_id: response.last_event_id,
+        };
+        self.registration = Arc::new(response);
+        Ok(self.registration.clone())
+    }
+
     async fn unregister(&self) -> Result<EmptyResponse, Error> {
         let params = UnregisterQueueRequest::from(&self.params);
         self.client
@@ -165,6 +396,17 @@ struct DispatcherParams {
     last_event_id: i32,
 }
 
+#[derive(Serialize)]
+struct EventsRequest<'a> {
+    queue_id: &'a str,
+    last_event_id: i32,
+    dont_block: Option<bool>,
+}
+
+fn is_bad_event_queue(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::Zulip(e) if e.is_bad_event_queue())
+}
+
 #[derive(Serialize)]
 struct UnregisterQueueRequest<'a> {
     queue_id: &'a str,
@@ -178,7 +420,7 @@ impl<'a> From<&'a DispatcherParams> for UnregisterQueueRequest<'a> {
     }
 }
 
-#[derive(Serialize, Default)]
+#[derive(Serialize, Debug, Default, Clone)]
 struct RegisterQueueRequest {
     apply_markdown: Option<bool>,
     client_gravatar: Option<bool>,
@@ -186,16 +428,19 @@ struct RegisterQueueRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(serialize_with = "crate::serialize")]
     event_types: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::serialize")]
+    fetch_event_types: Option<Vec<String>>,
     all_public_streams: Option<bool>,
     include_subscribers: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(serialize_with = "crate::serialize")]
-    narrow: Option<Vec<[String; 2]>>,
+    narrow: Option<Narrow>,
     #[serde(serialize_with = "crate::serialize")]
     client_capabilities: ClientCapabilities,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Debug, Clone)]
 struct ClientCapabilities {
     notification_settings_null: Option<bool>,
     bulk_message_deletion: Option<bool>,
@@ -216,13 +461,51 @@ impl Default for ClientCapabilities {
     }
 }
 
-#[derive(Deserialize)]
-struct RegisterQueueResponse {
+/// Response of the server to the registration of a queue.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RegisterQueueResponse {
     queue_id: String,
-    // zulip_version: String,
-    // zulip_feature_level: u16,
-    // zulip_merge_base: String,
+    zulip_version: String,
+    /// Zero for servers older than 3.0
+    #[serde(default)]
+    zulip_feature_level: u16,
+    zulip_merge_base: Option<String>,
+    event_queue_longpoll_timeout_seconds: Option<u64>,
     last_event_id: i32,
+    #[serde(flatten)]
+    state: InitialState,
+}
+
+impl RegisterQueueResponse {
+    pub fn queue_id(&self) -> &str {
+        self.queue_id.as_str()
+    }
+
+    pub fn last_event_id(&self) -> i32 {
+        self.last_event_id
+    }
+
+    pub fn zulip_version(&self) -> &str {
+        self.zulip_version.as_str()
+    }
+
+    pub fn zulip_feature_level(&self) -> u16 {
+        self.zulip_feature_level
+    }
+
+    pub fn zulip_merge_base(&self) -> Option<&str> {
+        self.zulip_merge_base.as_deref()
+    }
+
+    /// Time after which the server answers a long-poll with a heartbeat if
+    /// there are no events. Defaults to 90 seconds for old servers.
+    pub fn event_queue_longpoll_timeout(&self) -> Duration {
+        Duration::from_secs(self.event_queue_longpoll_timeout_seconds.unwrap_or(90))
+    }
+
+    pub fn initial_state(&self) -> &InitialState {
+        &self.state
+    }
 }
 
 #[derive(Deserialize)]
@@ -230,12 +513,11 @@ struct EventsResponse {
     events: Vec<Event>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Debug, Clone)]
 pub struct Event {
     id: i32,
-    #[serde(rename = "type")]
-    kind: String,
     op: Option<EventOp>,
+    kind: EventKind,
 }
 
 impl Event {
@@ -246,9 +528,59 @@ impl Event {
     pub fn op(&self) -> Option<EventOp> {
         self.op
     }
+
+    pub fn kind(&self) -> &EventKind {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> EventKind {
+        self.kind
+    }
+
+    pub fn is_heartbeat(&self) -> bool {
+        matches!(self.kind, EventKind::Heartbeat)
+    }
+
+    fn resynchronized(response: Arc<RegisterQueueResponse>) -> Self {
+        Self {
+            id: response.last_event_id,
+            op: None,
+            kind: EventKind::Resynchronized(response),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+        let id = value
+            .get("id")
+            .ok_or_else(|| de::Error::missing_field("id"))
+            .and_then(|id| i32::deserialize(id).map_err(de::Error::custom))?;
+        // Unknown operations must not prevent the event from being received
+        let op = value.get("op").and_then(|op| EventOp::deserialize(op).ok());
+        let kind = match EventKind::deserialize(&value) {
+            Ok(kind) => kind,
+            Err(e) => {
+                // A known type not matching its schema is likely a bug
+                let event_type = value
+                    .get("type")
+                    .and_then(|t| EventType::deserialize(t).ok());
+                if let Some(event_type) = event_type {
+                    log::warn!("failed to decode '{}' event {}: {}", event_type, id, e);
+                }
+                EventKind::Unknown(value)
+            }
+        };
+
+        Ok(Self { id, op, kind })
+    }
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventOp {
     #[serde(rename = "update")]
     Update,
@@ -284,12 +616,15 @@ pub enum EventOp {
     UpdateDict,
 }
 
-#[derive(Deserialize)]
-struct EmptyResponse {}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::narrow::{Filter, Is};
+    use crate::test_util::{
+        mock_method, MockErrorResponse, MockEventsResponse, MockRegisterResponse,
+    };
+    use serde_json::json;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn queue_builder() -> Result<(), Error> {
@@ -298,20 +633,250 @@ mod tests {
             .queue()
             .for_event("reaction")
             .for_event("reaction")
-            .narrow("stream", "general")
-            .narrow("is", "public")
-            .narrow("is", "public")
-            .narrow("is", "private");
+            .narrow(Filter::stream("general"))
+            .narrow(Filter::is(Is::Mentioned))
+            .narrow(Filter::is(Is::Mentioned))
+            .narrow(Filter::is(Is::Dm).negate())
+            .fetch_event_types(["realm_user", "subscription"])
+            .fetch_event_types(["realm_user"]);
 
         assert_eq!(
             builder.request.narrow,
-            Some(vec![
-                ["stream".into(), "general".into()],
-                ["is".into(), "public".into()],
-                ["is".into(), "private".into()],
-            ])
+            Some(
+                Narrow::new()
+                    .and(Filter::stream("general"))
+                    .and(Filter::is(Is::Mentioned))
+                    .and(Filter::is(Is::Dm).negate())
+            )
         );
         assert_eq!(builder.request.event_types, Some(vec!["reaction".into()]));
+        assert_eq!(
+            builder.request.fetch_event_types,
+            Some(vec!["realm_user".into(), "subscription".into()])
+        );
+
+        let result = builder.narrow(Filter::near(1)).register().await;
+        assert!(
+            matches!(result, Err(ref e) if e.is_build()),
+            "Expect unsupported narrow"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn initial_state() -> Result<(), Box<dyn std::error::Error>> {
+        let response = MockRegisterResponse::new().with_state(json!({
+            "max_message_id": 42,
+            "realm_users": [{
+                "user_id": 10,
+                "email": "iago@zulip.com",
+                "full_name": "Iago",
+                "is_admin": true,
+                "role": 200
+            }],
+            "unread_msgs": {
+                "count": 1,
+                "pms": [],
+                "streams": [{ "stream_id": 1, "topic": "test", "unread_message_ids": [42] }],
+                "huddles": [],
+                "mentions": [],
+                "old_unreads_missing": false
+            },
+            "realm_name": "Zulip Dev"
+        }));
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(response),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let queue = client.queue().register().await?;
+        assert_eq!(queue.zulip_version(), MockRegisterResponse::ZULIP_VERSION);
+        assert_eq!(
+            queue.zulip_feature_level(),
+            MockRegisterResponse::ZULIP_FEATURE_LEVEL
+        );
+
+        let state = queue.initial_state();
+        assert_eq!(state.max_message_id, Some(42));
+        assert_eq!(state.realm_users.as_ref().unwrap()[0].full_name, "Iago");
+        assert_eq!(state.unread_msgs.as_ref().unwrap().streams[0].topic, "test");
+        assert_eq!(state.subscriptions.as_ref().map(Vec::len), None);
+        assert_eq!(state.other.get("realm_name"), Some(&json!("Zulip Dev")));
+        assert!(!state.other.contains_key("result"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mismatched_initial_state() -> Result<(), Box<dyn std::error::Error>> {
+        let response = MockRegisterResponse::new().with_state(json!({
+            "max_message_id": 42,
+            "realm_users": [{ "user_id": "not a number" }],
+        }));
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(response),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let queue = client.queue().register().await?;
+        let state = queue.initial_state();
+        assert_eq!(state.max_message_id, Some(42));
+        assert!(state.realm_users.is_none());
+        assert!(state.other.contains_key("realm_users"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resilient_queue() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(
+                mock_method(
+                    "POST",
+                    ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                    Endpoint::REGISTER_EVENT_QUEUE,
+                )
+                .expect(2),
+            )
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(400).set_body_json(MockErrorResponse::bad_event_queue()),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(vec![json!({
+                    "id": 0,
+                    "type": "alert_words",
+                    "alert_words": []
+                })])),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let mut queue = client.queue().resilient(true).register().await?;
+
+        let events = queue.events().await?;
+        assert_eq!(events.len(), 1);
+        match events[0].kind() {
+            EventKind::Resynchronized(res) => {
+                assert_eq!(res.queue_id(), MockRegisterResponse::QUEUE_ID);
+                assert_eq!(res.last_event_id(), -1);
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+        assert_eq!(queue.last_event_id(), -1);
+
+        let events = queue.events().await?;
+        assert!(matches!(events[0].kind(), EventKind::AlertWords(_)));
+        assert_eq!(queue.last_event_id(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stalled_long_poll() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(200)
+                        .set_body_json(MockEventsResponse::heartbeat(0))
+                        .set_delay(Duration::from_secs(5)),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(MockEventsResponse::heartbeat(1)),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let mut queue = client
+            .queue()
+            .heartbeats(true)
+            .stall_timeout(Duration::from_millis(200))
+            .register()
+            .await?;
+
+        // The first poll is aborted and the heartbeat of the second one returned
+        let events = queue.events().await?;
+        assert!(events[0].is_heartbeat());
+        assert_eq!(queue.last_event_id(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn non_blocking_events() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+        server
+            .register(
+                Mock::given(matchers::method("GET"))
+                    .and(matchers::path(format!(
+                        "{}{}",
+                        Endpoint::BASE_API,
+                        Endpoint::EVENTS_QUEUE
+                    )))
+                    .and(matchers::query_param("dont_block", "true"))
+                    .respond_with(
+                        ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(vec![])),
+                    )
+                    .expect(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200)
+                    .set_body_json(MockEventsResponse::heartbeat(0))
+                    .set_delay(Duration::from_secs(5)),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let mut queue = client.queue().register().await?;
+
+        assert!(queue.try_events().await?.is_empty());
+        let events = queue.events_timeout(Duration::from_millis(200)).await?;
+        assert!(events.is_empty());
+        assert_eq!(queue.last_event_id(), -1);
         Ok(())
     }
 }
@@ -0,0 +1,290 @@
+use super::{Queue, QueueOptions, RegisterQueueRequest};
+use crate::narrow::Narrow;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+pub type StoreFuture<'a, T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'a>>;
+
+/// Progress of a [`Queue`], to resume it with
+/// [`Client::resume_queue`](crate::Client::resume_queue) after a restart.
+///
+/// It also keeps the registration parameters, so the queue can be registered
+/// again if it expired on the server meanwhile.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueueCheckpoint {
+    pub(super) queue_id: String,
+    pub(super) last_event_id: i32,
+    pub(super) zulip_version: String,
+    pub(super) zulip_feature_level: u16,
+    pub(super) event_queue_longpoll_timeout_seconds: Option<u64>,
+    pub(super) request: SavedRequest,
+    pub(super) options: QueueOptions,
+}
+
+impl QueueCheckpoint {
+    pub(super) fn new(queue: &Queue) -> Self {
+        let dispatcher = &queue.dispatcher;
+        let registration = &dispatcher.registration;
+
+        Self {
+            queue_id: dispatcher.queue_id().to_owned(),
+            last_event_id: dispatcher.last_event_id(),
+            zulip_version: registration.zulip_version.clone(),
+            zulip_feature_level: registration.zulip_feature_level,
+            event_queue_longpoll_timeout_seconds: registration.event_queue_longpoll_timeout_seconds,
+            request: SavedRequest::from(&dispatcher.request),
+            options: dispatcher.options.clone(),
+        }
+    }
+
+    pub fn queue_id(&self) -> &str {
+        self.queue_id.as_str()
+    }
+
+    pub fn last_event_id(&self) -> i32 {
+        self.last_event_id
+    }
+
+    pub fn zulip_version(&self) -> &str {
+        self.zulip_version.as_str()
+    }
+
+    pub fn zulip_feature_level(&self) -> u16 {
+        self.zulip_feature_level
+    }
+}
+
+/// Registration parameters kept in a checkpoint, stored as plain values
+/// rather than encoded as they are sent to the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(super) struct SavedRequest {
+    apply_markdown: Option<bool>,
+    client_gravatar: Option<bool>,
+    slim_presence: Option<bool>,
+    event_types: Option<Vec<String>>,
+    fetch_event_types: Option<Vec<String>>,
+    all_public_streams: Option<bool>,
+    include_subscribers: Option<bool>,
+    narrow: Option<Narrow>,
+}
+
+impl From<&RegisterQueueRequest> for SavedRequest {
+    fn from(request: &RegisterQueueRequest) -> Self {
+        Self {
+            apply_markdown: request.apply_markdown,
+            client_gravatar: request.client_gravatar,
+            slim_presence: request.slim_presence,
+            event_types: request.event_types.clone(),
+            fetch_event_types: request.fetch_event_types.clone(),
+            all_public_streams: request.all_public_streams,
+            include_subscribers: request.include_subscribers,
+            narrow: request.narrow.clone(),
+        }
+    }
+}
+
+impl From<SavedRequest> for RegisterQueueRequest {
+    fn from(request: SavedRequest) -> Self {
+        Self {
+            apply_markdown: request.apply_markdown,
+            client_gravatar: request.client_gravatar,
+            slim_presence: request.slim_presence,
+            event_types: request.event_types,
+            fetch_event_types: request.fetch_event_types,
+            all_public_streams: request.all_public_streams,
+            include_subscribers: request.include_subscribers,
+            narrow: request.narrow,
+            ..Default::default()
+        }
+    }
+}
+
+/// Storage of the checkpoint of a queue.
+pub trait CheckpointStore {
+    type Error: StdError + Send + Sync + 'static;
+
+    /// Returns the last saved checkpoint, if any.
+    fn load(&self) -> StoreFuture<'_, Option<QueueCheckpoint>, Self::Error>;
+
+    fn save<'a>(&'a self, checkpoint: &'a QueueCheckpoint) -> StoreFuture<'a, (), Self::Error>;
+}
+
+/// Stores a checkpoint as JSON in a file.
+#[derive(Debug, Clone)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Unique path next to the checkpoint, so that the rename is atomic and
+    /// concurrent saves do not share a temporary file.
+    fn tmp_path(&self) -> PathBuf {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        self.path.with_file_name(name)
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    type Error = io::Error;
+
+    fn load(&self) -> StoreFuture<'_, Option<QueueCheckpoint>, Self::Error> {
+        Box::pin(async move {
+            match tokio::fs::read(&self.path).await {
+                Ok(json) => Ok(Some(serde_json::from_slice(&json)?)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    fn save<'a>(&'a self, checkpoint: &'a QueueCheckpoint) -> StoreFuture<'a, (), Self::Error> {
+        Box::pin(async move {
+            let json = serde_json::to_vec(checkpoint)?;
+            // Write to a temporary file first, so that a crash while writing
+            // does not corrupt the previous checkpoint.
+            let tmp_path = self.tmp_path();
+            tokio::fs::write(&tmp_path, json).await?;
+            tokio::fs::rename(&tmp_path, &self.path).await
+        })
+    }
+}
+
+/// Keeps a checkpoint in memory.
+#[derive(Debug, Default)]
+pub struct MemoryCheckpointStore {
+    checkpoint: Mutex<Option<QueueCheckpoint>>,
+}
+
+impl MemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for MemoryCheckpointStore {
+    type Error = Infallible;
+
+    fn load(&self) -> StoreFuture<'_, Option<QueueCheckpoint>, Self::Error> {
+        let checkpoint = self.checkpoint.lock().unwrap().clone();
+        Box::pin(async move { Ok(checkpoint) })
+    }
+
+    fn save<'a>(&'a self, checkpoint: &'a QueueCheckpoint) -> StoreFuture<'a, (), Self::Error> {
+        self.checkpoint.lock().unwrap().replace(checkpoint.clone());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::Endpoint;
+    use crate::event::EventKind;
+    use crate::test_util::{
+        mock_method, MockErrorResponse, MockEventsResponse, MockRegisterResponse,
+    };
+    use crate::Client;
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn resume_queue() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(
+                mock_method(
+                    "POST",
+                    ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                    Endpoint::REGISTER_EVENT_QUEUE,
+                )
+                .expect(2),
+            )
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(vec![
+                        json!({ "id": 0, "type": "alert_words", "alert_words": [] }),
+                    ])),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(400).set_body_json(MockErrorResponse::bad_event_queue()),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let mut queue = client.queue().for_event("alert_words").register().await?;
+        queue.events().await?;
+
+        let path =
+            std::env::temp_dir().join(format!("rulip-checkpoint-{}.json", std::process::id()));
+        let file_store = FileCheckpointStore::new(&path);
+        let memory_store = MemoryCheckpointStore::new();
+        assert!(file_store.load().await?.is_none());
+        assert!(memory_store.load().await?.is_none());
+
+        file_store.save(&queue.checkpoint()).await?;
+        memory_store.save(&queue.checkpoint()).await?;
+        let checkpoint = file_store.load().await?.unwrap();
+        let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path)?)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(saved["request"]["event_types"], json!(["alert_words"]));
+        assert_eq!(checkpoint.queue_id(), MockRegisterResponse::QUEUE_ID);
+        assert_eq!(checkpoint.last_event_id(), 0);
+        assert_eq!(
+            checkpoint.zulip_version(),
+            MockRegisterResponse::ZULIP_VERSION
+        );
+        assert_eq!(
+            memory_store.load().await?.unwrap().last_event_id(),
+            checkpoint.last_event_id()
+        );
+
+        // The queue expired, so it is registered again with the same parameters
+        let mut queue = client.resume_queue(checkpoint);
+        assert_eq!(queue.last_event_id(), 0);
+        let events = queue.events().await?;
+        assert!(matches!(events[0].kind(), EventKind::Resynchronized(_)));
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].body, requests[3].body);
+        Ok(())
+    }
+
+    #[test]
+    fn tmp_path() {
+        let json_store = FileCheckpointStore::new("/var/lib/bot/checkpoint.json");
+        let bak_store = FileCheckpointStore::new("/var/lib/bot/checkpoint.bak");
+        let tmp_path = json_store.tmp_path();
+
+        assert_eq!(tmp_path.parent(), json_store.path.parent());
+        assert_ne!(tmp_path, json_store.tmp_path());
+        assert_ne!(tmp_path, bak_store.tmp_path());
+    }
+}
@@ -0,0 +1,702 @@
+use super::{EventOp, RegisterQueueResponse};
+use crate::message::{Draft, Message, MessageType, PropagateMode, Reaction, ScheduledMessage};
+use crate::realm::{CustomProfileField, RealmEmoji};
+use crate::stream::{Stream, Subscription, SubscriptionProperty, UserTopic};
+use crate::user::{User, UserGroup, UserSetting};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Typed payload of an event, discriminated by its `type` field.
+///
+/// Events whose type is not known by this library, or whose payload does not
+/// match the expected schema, are kept as [`EventKind::Unknown`] with the raw
+/// JSON sent by the server.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EventKind {
+    Message(MessageEvent),
+    UpdateMessage(UpdateMessageEvent),
+    DeleteMessage(DeleteMessageEvent),
+    Reaction(ReactionEvent),
+    UpdateMessageFlags(UpdateMessageFlagsEvent),
+    Typing(TypingEvent),
+    Subscription(SubscriptionEvent),
+    Stream(StreamEvent),
+    RealmUser(RealmUserEvent),
+    UserGroup(UserGroupEvent),
+    Realm(RealmEvent),
+    RealmEmoji(RealmEmojiEvent),
+    CustomProfileFields(CustomProfileFieldsEvent),
+    AlertWords(AlertWordsEvent),
+    Presence(PresenceEvent),
+    UserStatus(UserStatusEvent),
+    MutedUsers(MutedUsersEvent),
+    DefaultStreams(DefaultStreamsEvent),
+    Restart(RestartEvent),
+    ScheduledMessages(ScheduledMessagesEvent),
+    Drafts(DraftsEvent),
+    UserTopic(UserTopic),
+    UserSettings(UserSettingsEvent),
+    Heartbeat,
+    /// Not sent by the server, but emitted by a
+    /// [`resilient`](super::QueueBuilder::resilient) queue after registering
+    /// again. Events may have been missed, so any local state should be
+    /// reloaded.
+    #[serde(skip)]
+    Resynchronized(Arc<RegisterQueueResponse>),
+    #[serde(skip)]
+    Unknown(Value),
+}
+
+impl EventKind {
+    /// Type of the event, or `None` if it was not sent by the server or is
+    /// unknown.
+    pub fn event_type(&self) -> Option<EventType> {
+        let event_type = match self {
+            EventKind::Message(_) => EventType::Message,
+            EventKind::UpdateMessage(_) => EventType::UpdateMessage,
+            EventKind::DeleteMessage(_) => EventType::DeleteMessage,
+            EventKind::Reaction(_) => EventType::Reaction,
+            EventKind::UpdateMessageFlags(_) => EventType::UpdateMessageFlags,
+            EventKind::Typing(_) => EventType::Typing,
+            EventKind::Subscription(_) => EventType::Subscription,
+            EventKind::Stream(_) => EventType::Stream,
+            EventKind::RealmUser(_) => EventType::RealmUser,
+            EventKind::UserGroup(_) => EventType::UserGroup,
+            EventKind::Realm(_) => EventType::Realm,
+            EventKind::RealmEmoji(_) => EventType::RealmEmoji,
+            EventKind::CustomProfileFields(_) => EventType::CustomProfileFields,
+            EventKind::AlertWords(_) => EventType::AlertWords,
+            EventKind::Presence(_) => EventType::Presence,
+            EventKind::UserStatus(_) => EventType::UserStatus,
+            EventKind::MutedUsers(_) => EventType::MutedUsers,
+            EventKind::DefaultStreams(_) => EventType::DefaultStreams,
+            EventKind::Restart(_) => EventType::Restart,
+            EventKind::ScheduledMessages(_) => EventType::ScheduledMessages,
+            EventKind::Drafts(_) => EventType::Drafts,
+            EventKind::UserTopic(_) => EventType::UserTopic,
+            EventKind::UserSettings(_) => EventType::UserSettings,
+            EventKind::Heartbeat => EventType::Heartbeat,
+            EventKind::Resynchronized(_) | EventKind::Unknown(_) => return None,
+        };
+        Some(event_type)
+    }
+}
+
+/// Event types known by this library, as used to register a queue.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EventType {
+    Message,
+    UpdateMessage,
+    DeleteMessage,
+    Reaction,
+    UpdateMessageFlags,
+    Typing,
+    Subscription,
+    Stream,
+    RealmUser,
+    UserGroup,
+    Realm,
+    RealmEmoji,
+    CustomProfileFields,
+    AlertWords,
+    Presence,
+    UserStatus,
+    MutedUsers,
+    DefaultStreams,
+    Restart,
+    ScheduledMessages,
+    Drafts,
+    UserTopic,
+    UserSettings,
+    Heartbeat,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Message => "message",
+            EventType::UpdateMessage => "update_message",
+            EventType::DeleteMessage => "delete_message",
+            EventType::Reaction => "reaction",
+            EventType::UpdateMessageFlags => "update_message_flags",
+            EventType::Typing => "typing",
+            EventType::Subscription => "subscription",
+            EventType::Stream => "stream",
+            EventType::RealmUser => "realm_user",
+            EventType::UserGroup => "user_group",
+            EventType::Realm => "realm",
+            EventType::RealmEmoji => "realm_emoji",
+            EventType::CustomProfileFields => "custom_profile_fields",
+            EventType::AlertWords => "alert_words",
+            EventType::Presence => "presence",
+            EventType::UserStatus => "user_status",
+            EventType::MutedUsers => "muted_users",
+            EventType::DefaultStreams => "default_streams",
+            EventType::Restart => "restart",
+            EventType::ScheduledMessages => "scheduled_messages",
+            EventType::Drafts => "drafts",
+            EventType::UserTopic => "user_topic",
+            EventType::UserSettings => "user_settings",
+            EventType::Heartbeat => "heartbeat",
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<EventType> for String {
+    fn from(value: EventType) -> Self {
+        value.as_str().to_owned()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MessageEvent {
+    pub message: Message,
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateMessageEvent {
+    /// Missing when the update was done by the server, e.g. inline URL
+    /// previews.
+    pub user_id: Option<u64>,
+    #[serde(default)]
+    pub rendering_only: bool,
+    pub message_id: u64,
+    pub message_ids: Vec<u64>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    pub edit_timestamp: i64,
+    pub stream_name: Option<String>,
+    pub stream_id: Option<u64>,
+    pub new_stream_id: Option<u64>,
+    pub propagate_mode: Option<PropagateMode>,
+    #[serde(rename = "orig_subject")]
+    pub orig_topic: Option<String>,
+    #[serde(rename = "subject")]
+    pub topic: Option<String>,
+    pub orig_content: Option<String>,
+    pub orig_rendered_content: Option<String>,
+    pub content: Option<String>,
+    pub rendered_content: Option<String>,
+    pub is_me_message: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeleteMessageEvent {
+    pub message_type: MessageType,
+    /// Present when the client declared `bulk_message_deletion`.
+    pub message_ids: Option<Vec<u64>>,
+    pub message_id: Option<u64>,
+    pub stream_id: Option<u64>,
+    pub topic: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReactionEvent {
+    pub op: EventOp,
+    pub message_id: u64,
+    #[serde(flatten)]
+    pub reaction: Reaction,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UpdateMessageFlagsEvent {
+    pub op: EventOp,
+    pub flag: String,
+    pub messages: Vec<u64>,
+    /// Whether the flag was added to all messages. Only set when adding.
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TypingEvent {
+    pub op: EventOp,
+    pub message_type: MessageType,
+    pub sender: TypingUser,
+    /// Recipients of a direct message.
+    pub recipients: Option<Vec<TypingUser>>,
+    pub stream_id: Option<u64>,
+    pub topic: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TypingUser {
+    pub user_id: u64,
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SubscriptionEvent {
+    Add {
+        subscriptions: Vec<Subscription>,
+    },
+    Remove {
+        subscriptions: Vec<StreamRef>,
+    },
+    Update {
+        stream_id: u64,
+        #[serde(flatten)]
+        property: SubscriptionProperty,
+    },
+    PeerAdd {
+        stream_ids: Vec<u64>,
+        user_ids: Vec<u64>,
+    },
+    PeerRemove {
+        stream_ids: Vec<u64>,
+        user_ids: Vec<u64>,
+    },
+}
+
+/// Minimal reference to a stream, as sent when it is removed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StreamRef {
+    pub stream_id: u64,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Create {
+        streams: Vec<Stream>,
+    },
+    Delete {
+        streams: Vec<StreamRef>,
+    },
+    Update {
+        stream_id: u64,
+        name: String,
+        property: String,
+        value: Value,
+        rendered_description: Option<String>,
+        history_public_to_subscribers: Option<bool>,
+        is_web_public: Option<bool>,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RealmUserEvent {
+    Add { person: User },
+    Remove { person: RemovedUser },
+    Update { person: UserUpdate },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RemovedUser {
+    pub user_id: u64,
+    pub full_name: String,
+}
+
+/// Partial update of a user. Only the changed properties are present in
+/// `data`, keyed by their name.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserUpdate {
+    pub user_id: u64,
+    #[serde(flatten)]
+    pub data: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum UserGroupEvent {
+    Add {
+        group: UserGroup,
+    },
+    Update {
+        group_id: u64,
+        data: HashMap<String, Value>,
+    },
+    AddMembers {
+        group_id: u64,
+        user_ids: Vec<u64>,
+    },
+    RemoveMembers {
+        group_id: u64,
+        user_ids: Vec<u64>,
+    },
+    AddSubgroups {
+        group_id: u64,
+        direct_subgroup_ids: Vec<u64>,
+    },
+    RemoveSubgroups {
+        group_id: u64,
+        direct_subgroup_ids: Vec<u64>,
+    },
+    Remove {
+        group_id: u64,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum RealmEvent {
+    Update {
+        property: String,
+        value: Value,
+    },
+    UpdateDict {
+        property: String,
+        data: HashMap<String, Value>,
+    },
+    Deactivated {
+        realm_id: u64,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RealmEmojiEvent {
+    pub op: EventOp,
+    pub realm_emoji: HashMap<String, RealmEmoji>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CustomProfileFieldsEvent {
+    pub fields: Vec<CustomProfileField>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertWordsEvent {
+    pub alert_words: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PresenceEvent {
+    pub user_id: u64,
+    pub email: Option<String>,
+    pub server_timestamp: f64,
+    /// Presence per client, keyed by client name.
+    pub presence: HashMap<String, ClientPresence>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClientPresence {
+    pub client: Option<String>,
+    pub status: String,
+    pub timestamp: i64,
+    #[serde(default)]
+    pub pushable: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserStatusEvent {
+    pub user_id: u64,
+    pub away: Option<bool>,
+    pub status_text: Option<String>,
+    pub emoji_name: Option<String>,
+    pub emoji_code: Option<String>,
+    pub reaction_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MutedUsersEvent {
+    pub muted_users: Vec<MutedUser>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MutedUser {
+    pub id: u64,
+    pub timestamp: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DefaultStreamsEvent {
+    pub default_streams: Vec<Stream>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RestartEvent {
+    pub zulip_version: String,
+    pub zulip_feature_level: u16,
+    pub zulip_merge_base: Option<String>,
+    pub server_generation: i64,
+    #[serde(default)]
+    pub immediate: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ScheduledMessagesEvent {
+    Add {
+        scheduled_messages: Vec<ScheduledMessage>,
+    },
+    Update {
+        scheduled_message: ScheduledMessage,
+    },
+    Remove {
+        scheduled_message_id: u64,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DraftsEvent {
+    Add { drafts: Vec<Draft> },
+    Update { draft: Draft },
+    Remove { draft_id: u64 },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserSettingsEvent {
+    pub op: EventOp,
+    #[serde(flatten)]
+    pub setting: UserSetting,
+    /// Name of the new language, when `default_language` changed.
+    pub language_name: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Event;
+    use super::*;
+    use crate::message::ReactionType;
+    use crate::stream::VisibilityPolicy;
+    use serde_json::json;
+
+    fn event(value: Value) -> Event {
+        serde_json::from_value(value).expect("Event should always deserialize")
+    }
+
+    #[test]
+    fn typed_events() {
+        let evt = event(json!({
+            "id": 0,
+            "type": "message",
+            "flags": ["mentioned"],
+            "message": {
+                "id": 31,
+                "sender_id": 10,
+                "sender_email": "iago@zulip.com",
+                "sender_full_name": "Iago",
+                "avatar_url": null,
+                "content": "<p>Hello</p>",
+                "content_type": "text/html",
+                "type": "stream",
+                "display_recipient": "Denmark",
+                "stream_id": 1,
+                "recipient_id": 12,
+                "subject": "test",
+                "timestamp": 1594825416,
+                "reactions": []
+            }
+        }));
+        assert_eq!(evt.id(), 0);
+        match evt.kind() {
+            EventKind::Message(e) => {
+                assert_eq!(e.message.id, 31);
+                assert_eq!(e.message.kind, MessageType::Stream);
+                assert_eq!(e.message.topic, "test");
+                assert_eq!(e.flags, vec!["mentioned".to_owned()]);
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+
+        let evt = event(json!({
+            "id": 1,
+            "type": "reaction",
+            "op": "add",
+            "user_id": 10,
+            "message_id": 32,
+            "emoji_name": "tada",
+            "emoji_code": "1f389",
+            "reaction_type": "unicode_emoji"
+        }));
+        assert_eq!(evt.op(), Some(EventOp::Add));
+        match evt.kind() {
+            EventKind::Reaction(e) => {
+                assert_eq!(e.reaction.emoji_name, "tada");
+                assert_eq!(e.reaction.reaction_type, ReactionType::UnicodeEmoji);
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+
+        let evt = event(json!({
+            "id": 2,
+            "type": "subscription",
+            "op": "peer_add",
+            "stream_ids": [1, 2],
+            "user_ids": [10]
+        }));
+        assert!(matches!(
+            evt.kind(),
+            EventKind::Subscription(SubscriptionEvent::PeerAdd { user_ids, .. }) if user_ids == &[10]
+        ));
+
+        let evt = event(json!({ "id": 3, "type": "heartbeat" }));
+        assert!(matches!(evt.kind(), EventKind::Heartbeat));
+    }
+
+    #[test]
+    fn subscription_update_events() {
+        let evt = event(json!({
+            "id": 0,
+            "type": "subscription",
+            "op": "update",
+            "stream_id": 1,
+            "property": "color",
+            "value": "#f00f00"
+        }));
+        assert!(matches!(
+            evt.kind(),
+            EventKind::Subscription(SubscriptionEvent::Update {
+                stream_id: 1,
+                property: SubscriptionProperty::Color(color),
+            }) if color == "#f00f00"
+        ));
+
+        let evt = event(json!({
+            "id": 1,
+            "type": "subscription",
+            "op": "update",
+            "stream_id": 1,
+            "property": "some_new_prop",
+            "value": 42
+        }));
+        assert!(matches!(
+            evt.kind(),
+            EventKind::Subscription(SubscriptionEvent::Update {
+                stream_id: 1,
+                property: SubscriptionProperty::Other { property, value },
+            }) if property == "some_new_prop" && value == &json!(42)
+        ));
+    }
+
+    #[test]
+    fn scheduled_messages_events() {
+        let evt = event(json!({
+            "id": 0, "type": "scheduled_messages", "op": "update",
+            "scheduled_message": {
+                "scheduled_message_id": 27, "type": "private", "to": [10],
+                "content": "Hi", "rendered_content": "<p>Hi</p>",
+                "scheduled_delivery_timestamp": 1681662420, "failed": false
+            }
+        }));
+        match evt.kind() {
+            EventKind::ScheduledMessages(ScheduledMessagesEvent::Update { scheduled_message }) => {
+                assert_eq!(scheduled_message.kind, MessageType::Direct);
+                assert!(scheduled_message.topic.is_none());
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+
+        let evt = event(json!({
+            "id": 1, "type": "scheduled_messages", "op": "remove", "scheduled_message_id": 27
+        }));
+        assert!(matches!(
+            evt.kind(),
+            EventKind::ScheduledMessages(ScheduledMessagesEvent::Remove {
+                scheduled_message_id: 27
+            })
+        ));
+    }
+
+    #[test]
+    fn drafts_events() {
+        let evt = event(json!({
+            "id": 0, "type": "drafts", "op": "add",
+            "drafts": [{
+                "id": 17, "type": "private", "to": [10], "topic": "",
+                "content": "Hi", "timestamp": 1595479019
+            }]
+        }));
+        match evt.kind() {
+            EventKind::Drafts(DraftsEvent::Add { drafts }) => {
+                assert_eq!(drafts[0].id, Some(17));
+                assert_eq!(drafts[0].to, vec![10]);
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+
+        let evt = event(json!({ "id": 1, "type": "drafts", "op": "remove", "draft_id": 17 }));
+        assert!(matches!(
+            evt.kind(),
+            EventKind::Drafts(DraftsEvent::Remove { draft_id: 17 })
+        ));
+    }
+
+    #[test]
+    fn user_topic_events() {
+        let evt = event(json!({
+            "id": 0, "type": "user_topic", "stream_id": 1, "topic_name": "Incident 42",
+            "last_updated": 1594825416, "visibility_policy": 3
+        }));
+        match evt.kind() {
+            EventKind::UserTopic(e) => {
+                assert_eq!(e.topic_name, "Incident 42");
+                assert_eq!(e.visibility_policy, VisibilityPolicy::Followed);
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn user_settings_events() {
+        let evt = event(json!({
+            "id": 0, "type": "user_settings", "op": "update",
+            "property": "default_language", "value": "fr", "language_name": "French"
+        }));
+        match evt.kind() {
+            EventKind::UserSettings(e) => {
+                assert_eq!(e.setting, UserSetting::DefaultLanguage("fr".into()));
+                assert_eq!(e.language_name.as_deref(), Some("French"));
+            }
+            kind => panic!("Unexpected event {:?}", kind),
+        }
+
+        // Settings and values unknown to this library are kept raw
+        for (property, value) in [("web_font_size_px", json!(16)), ("color_scheme", json!(4))] {
+            let evt = event(json!({
+                "id": 1, "type": "user_settings", "op": "update",
+                "property": property, "value": value
+            }));
+            match evt.kind() {
+                EventKind::UserSettings(e) => assert_eq!(
+                    e.setting,
+                    UserSetting::Other {
+                        property: property.into(),
+                        value
+                    }
+                ),
+                kind => panic!("Unexpected event {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_events() {
+        // Unknown type
+        let raw = json!({ "id": 4, "type": "not_yet_invented", "foo": "bar" });
+        let evt = event(raw.clone());
+        assert_eq!(evt.id(), 4);
+        assert!(matches!(evt.kind(), EventKind::Unknown(v) if v == &raw));
+
+        // Known type with an unexpected payload
+        let raw = json!({ "id": 5, "type": "reaction", "op": "add", "emoji_name": 1 });
+        let evt = event(raw.clone());
+        assert_eq!(evt.op(), Some(EventOp::Add));
+        assert!(matches!(evt.kind(), EventKind::Unknown(v) if v == &raw));
+
+        // Unknown op
+        let evt =
+            event(json!({ "id": 6, "type": "alert_words", "op": "new_op", "alert_words": [] }));
+        assert_eq!(evt.op(), None);
+        assert!(matches!(evt.kind(), EventKind::AlertWords(_)));
+    }
+}
@@ -0,0 +1,177 @@
+use super::stream::forward;
+use super::{Event, Queue, STREAM_CAPACITY};
+use crate::Error;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Set of queues, possibly from different clients, polled concurrently as a
+/// single stream.
+///
+/// Each queue is identified by a key of your choice, e.g. the name of its
+/// realm, which tags the events it yields.
+pub struct QueueSet<K> {
+    queues: Vec<(K, Queue)>,
+}
+
+impl<K> QueueSet<K>
+where
+    K: Clone + Send + 'static,
+{
+    pub fn new() -> Self {
+        Self { queues: Vec::new() }
+    }
+
+    pub fn insert(&mut self, key: K, queue: Queue) {
+        self.queues.push((key, queue));
+    }
+
+    pub fn with(mut self, key: K, queue: Queue) -> Self {
+        self.insert(key, queue);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.is_empty()
+    }
+
+    /// Converts the set into a [`Stream`](futures_core::Stream) of the events
+    /// of all queues, buffering up to 64 events.
+    ///
+    /// Must be called within a Tokio runtime, since events are polled by a
+    /// background task per queue.
+    pub fn into_stream(self) -> QueueSetStream<K> {
+        self.into_stream_with_capacity(STREAM_CAPACITY)
+    }
+
+    /// Like [`QueueSet::into_stream`], but buffering up to `capacity` events.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn into_stream_with_capacity(self, capacity: usize) -> QueueSetStream<K> {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let tasks = self
+            .queues
+            .into_iter()
+            .map(|(key, queue)| {
+                let fut = forward(queue.dispatcher, sender.clone(), move |res| {
+                    (key.clone(), res)
+                });
+                tokio::spawn(fut)
+            })
+            .collect();
+
+        QueueSetStream { receiver, tasks }
+    }
+}
+
+impl<K> Default for QueueSet<K>
+where
+    K: Clone + Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stream of the events of a [`QueueSet`], tagged with the key of their queue.
+///
+/// Errors are isolated: a queue stops being polled after yielding an error,
+/// while the others go on. The stream ends once every queue has stopped.
+pub struct QueueSetStream<K> {
+    receiver: mpsc::Receiver<(K, Result<Event, Error>)>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl<K> Stream for QueueSetStream<K> {
+    type Item = (K, Result<Event, Error>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<K> Drop for QueueSetStream<K> {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::Endpoint;
+    use crate::test_util::{
+        mock_method, MockErrorResponse, MockEventsResponse, MockRegisterResponse,
+    };
+    use crate::Client;
+    use futures::StreamExt;
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    async fn realm(event_count: i32) -> MockServer {
+        let events = (0..event_count)
+            .map(|id| json!({ "id": id, "type": "alert_words", "alert_words": [] }))
+            .collect();
+
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(events)),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(400).set_body_json(MockErrorResponse::bad_event_queue()),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn queue_set() -> Result<(), Box<dyn std::error::Error>> {
+        let (foo, bar) = (realm(1).await, realm(2).await);
+        let foo_client = Client::build(foo.uri()).init().await?;
+        let bar_client = Client::build(bar.uri()).init().await?;
+
+        let set = QueueSet::new()
+            .with("foo", foo_client.queue().register().await?)
+            .with("bar", bar_client.queue().register().await?);
+        assert_eq!(set.len(), 2);
+
+        let items: Vec<_> = set.into_stream().collect().await;
+        let summary = |key| {
+            let results: Vec<_> = items.iter().filter(|(k, _)| *k == key).collect();
+            let events = results.iter().filter(|(_, r)| r.is_ok()).count();
+            (events, results.len() - events)
+        };
+
+        // Each queue yields its own events and error
+        assert_eq!(summary("foo"), (1, 1));
+        assert_eq!(summary("bar"), (2, 1));
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use super::MutedUser;
+use crate::message::{Draft, ScheduledMessage};
+use crate::realm::{CustomProfileField, RealmEmoji};
+use crate::stream::{Stream, Subscription, UserTopic};
+use crate::user::{User, UserGroup, UserSettings};
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Snapshot of the realm data sent by the server when a queue is registered.
+///
+/// Each field is only present when its event type was requested with
+/// [`QueueBuilder::fetch_event_types`](super::QueueBuilder::fetch_event_types),
+/// or by default with [`QueueBuilder::for_event`](super::QueueBuilder::for_event).
+/// Fields that do not match the model of this library, e.g. because of a
+/// newer server, are left empty and kept raw in [`InitialState::other`].
+#[derive(Debug, Clone, Default)]
+pub struct InitialState {
+    pub max_message_id: Option<u64>,
+    pub realm_users: Option<Vec<User>>,
+    pub realm_non_active_users: Option<Vec<User>>,
+    pub cross_realm_bots: Option<Vec<User>>,
+    pub realm_user_groups: Option<Vec<UserGroup>>,
+    pub streams: Option<Vec<Stream>>,
+    pub subscriptions: Option<Vec<Subscription>>,
+    pub unsubscribed: Option<Vec<Subscription>>,
+    pub never_subscribed: Option<Vec<Stream>>,
+    pub unread_msgs: Option<UnreadMessages>,
+    pub realm_emoji: Option<HashMap<String, RealmEmoji>>,
+    pub custom_profile_fields: Option<Vec<CustomProfileField>>,
+    pub alert_words: Option<Vec<String>>,
+    pub muted_users: Option<Vec<MutedUser>>,
+    pub scheduled_messages: Option<Vec<ScheduledMessage>>,
+    pub drafts: Option<Vec<Draft>>,
+    pub user_topics: Option<Vec<UserTopic>>,
+    pub user_settings: Option<UserSettings>,
+    /// Any other data sent by the server that is not typed by this library,
+    /// e.g. realm settings, keyed by name.
+    pub other: HashMap<String, Value>,
+}
+
+impl<'de> Deserialize<'de> for InitialState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut other = HashMap::<String, Value>::deserialize(deserializer)?;
+        // Fields of the response itself, not of the realm
+        other.remove("result");
+        other.remove("msg");
+        Ok(Self {
+            max_message_id: take(&mut other, "max_message_id"),
+            realm_users: take(&mut other, "realm_users"),
+            realm_non_active_users: take(&mut other, "realm_non_active_users"),
+            cross_realm_bots: take(&mut other, "cross_realm_bots"),
+            realm_user_groups: take(&mut other, "realm_user_groups"),
+            streams: take(&mut other, "streams"),
+            subscriptions: take(&mut other, "subscriptions"),
+            unsubscribed: take(&mut other, "unsubscribed"),
+            never_subscribed: take(&mut other, "never_subscribed"),
+            unread_msgs: take(&mut other, "unread_msgs"),
+            realm_emoji: take(&mut other, "realm_emoji"),
+            custom_profile_fields: take(&mut other, "custom_profile_fields"),
+            alert_words: take(&mut other, "alert_words"),
+            muted_users: take(&mut other, "muted_users"),
+            scheduled_messages: take(&mut other, "scheduled_messages"),
+            drafts: take(&mut other, "drafts"),
+            user_topics: take(&mut other, "user_topics"),
+            user_settings: take(&mut other, "user_settings"),
+            other,
+        })
+    }
+}
+
+/// Decodes a field on its own, so a mismatch does not fail the whole state.
+fn take<T: DeserializeOwned>(other: &mut HashMap<String, Value>, key: &str) -> Option<T> {
+    let value = other.remove(key)?;
+    if value.is_null() {
+        return None;
+    }
+
+    match T::deserialize(&value) {
+        Ok(field) => Some(field),
+        Err(e) => {
+            log::warn!("failed to decode initial state '{}': {}", key, e);
+            other.insert(key.to_owned(), value);
+            None
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnreadMessages {
+    pub count: u64,
+    #[serde(default)]
+    pub pms: Vec<UnreadDirectMessages>,
+    #[serde(default)]
+    pub streams: Vec<UnreadStreamMessages>,
+    #[serde(default)]
+    pub huddles: Vec<UnreadHuddleMessages>,
+    #[serde(default)]
+    pub mentions: Vec<u64>,
+    #[serde(default)]
+    pub old_unreads_missing: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnreadDirectMessages {
+    /// Missing on servers older than feature level 119, which only send
+    /// `sender_id`.
+    pub other_user_id: Option<u64>,
+    pub sender_id: Option<u64>,
+    pub unread_message_ids: Vec<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnreadStreamMessages {
+    pub stream_id: u64,
+    pub topic: String,
+    pub unread_message_ids: Vec<u64>,
+}
+
+/// Unread messages of a group direct conversation.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnreadHuddleMessages {
+    /// Comma-separated IDs of the users in the conversation.
+    pub user_ids_string: String,
+    pub unread_message_ids: Vec<u64>,
+}
@@ -0,0 +1,437 @@
+use super::{
+    DeleteMessageEvent, Event, EventKind, EventType, MessageEvent, QueueBuilder, ReactionEvent,
+    RegisterQueueResponse, TypingEvent, UpdateMessageEvent,
+};
+use crate::Error;
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+type HandlerError = Box<dyn StdError + Send + Sync>;
+type HandlerFuture = Pin<Box<dyn Future<Output = Result<(), HandlerError>> + Send>>;
+type Handler = Arc<dyn Fn(Event) -> HandlerFuture + Send + Sync>;
+
+/// What to do when an event handler fails. Errors of the queue itself always
+/// stop the [`Router`], so expired queues should be made
+/// [`resilient`](QueueBuilder::resilient) to be registered again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Log the error and keep handling events.
+    Log,
+    /// Stop handling events, returning successfully.
+    Stop,
+    /// Stop handling events, returning the error.
+    Propagate,
+}
+
+/// Dispatches the events of a queue to async handlers registered by type.
+///
+/// The queue is only subscribed to the event types that have a handler.
+/// Handlers run concurrently, up to the configured
+/// [`concurrency`](Router::concurrency), so the order in which they complete
+/// is not guaranteed unless it is set to one.
+pub struct Router {
+    handlers: BTreeMap<EventType, Vec<Handler>>,
+    resynchronized_handlers: Vec<Handler>,
+    concurrency: usize,
+    error_policy: ErrorPolicy,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            handlers: BTreeMap::new(),
+            resynchronized_handlers: Vec::new(),
+            concurrency: 1,
+            error_policy: ErrorPolicy::Log,
+        }
+    }
+
+    /// Maximum number of handlers running at the same time. Defaults to one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is zero.
+    pub fn concurrency(mut self, value: usize) -> Self {
+        assert!(value > 0, "concurrency must be greater than zero");
+        self.concurrency = value;
+        self
+    }
+
+    /// Defaults to [`ErrorPolicy::Log`].
+    pub fn error_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.error_policy = policy;
+        self
+    }
+
+    /// Registers a handler for every event of the given type.
+    pub fn on<F, Fut, E>(mut self, event_type: EventType, handler: F) -> Self
+    where
+        F: Fn(Event) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        let handler: Handler = Arc::new(move |event| {
+            let fut = handler(event);
+            Box::pin(async move { fut.await.map_err(Into::into) })
+        });
+        self.handlers.entry(event_type).or_default().push(handler);
+        self
+    }
+
+    pub fn on_message<F, Fut, E>(self, handler: F) -> Self
+    where
+        F: Fn(MessageEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on_kind(EventType::Message, handler, |kind| match kind {
+            EventKind::Message(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn on_update_message<F, Fut, E>(self, handler: F) -> Self
+    where
+        F: Fn(UpdateMessageEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on_kind(EventType::UpdateMessage, handler, |kind| match kind {
+            EventKind::UpdateMessage(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn on_delete_message<F, Fut, E>(self, handler: F) -> Self
+    where
+        F: Fn(DeleteMessageEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on_kind(EventType::DeleteMessage, handler, |kind| match kind {
+            EventKind::DeleteMessage(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn on_reaction<F, Fut, E>(self, handler: F) -> Self
+    where
+        F: Fn(ReactionEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on_kind(EventType::Reaction, handler, |kind| match kind {
+            EventKind::Reaction(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    pub fn on_typing<F, Fut, E>(self, handler: F) -> Self
+    where
+        F: Fn(TypingEvent) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on_kind(EventType::Typing, handler, |kind| match kind {
+            EventKind::Typing(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Registers a handler for when a [`resilient`](QueueBuilder::resilient)
+    /// queue is registered again, since events may have been missed.
+    pub fn on_resynchronized<F, Fut, E>(mut self, handler: F) -> Self
+    where
+        F: Fn(Arc<RegisterQueueResponse>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        let handler: Handler = Arc::new(move |event: Event| {
+            let fut = match event.into_kind() {
+                EventKind::Resynchronized(response) => Some(handler(response)),
+                _ => None,
+            };
+            Box::pin(async move {
+                match fut {
+                    Some(fut) => fut.await.map_err(Into::into),
+                    None => Ok(()),
+                }
+            })
+        });
+        self.resynchronized_handlers.push(handler);
+        self
+    }
+
+    fn on_kind<T, F, Fut, E>(
+        self,
+        event_type: EventType,
+        handler: F,
+        extract: fn(EventKind) -> Option<T>,
+    ) -> Self
+    where
+        T: Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<HandlerError>,
+    {
+        self.on(event_type, move |event: Event| {
+            // Events are only dispatched to the handlers of their own type
+            let fut = extract(event.into_kind()).map(&handler);
+            async move {
+                match fut {
+                    Some(fut) => fut.await,
+                    None => Ok(()),
+                }
+            }
+        })
+    }
+
+    /// Event types with at least one handler.
+    pub fn event_types(&self) -> impl Iterator<Item = EventType> + '_ {
+        self.handlers.keys().copied()
+    }
+
+    /// Registers a queue for the event types with handlers and dispatches its
+    /// events until a handler fails, according to the error policy, or the
+    /// queue returns an error.
+    ///
+    /// Handlers still running when it returns are awaited, then the queue is
+    /// deleted from the server. Returns immediately, without registering a
+    /// queue, if there are no handlers by event type.
+    pub async fn run(self, builder: QueueBuilder) -> Result<(), Error> {
+        // The queue would be subscribed to every event type
+        if self.handlers.is_empty() {
+            return Ok(());
+        }
+
+        let builder = self
+            .event_types()
+            .fold(builder, |builder, event_type| builder.for_event(event_type));
+        let mut queue = builder.register().await?;
+
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let (error_tx, mut error_rx) = mpsc::unbounded_channel();
+
+        let result = 'outer: loop {
+            // Keep listening to handler errors while waiting for events
+            let events = tokio::select! {
+                res = queue.events() => match res {
+                    Ok(events) => events,
+                    Err(e) => break Err(e),
+                },
+                Some(e) = error_rx.recv() => match self.handle_error(e) {
+                    Some(result) => break result,
+                    None => continue,
+                },
+            };
+
+            for event in events {
+                let handlers = match event.kind() {
+                    EventKind::Resynchronized(_) => Some(&self.resynchronized_handlers),
+                    kind => kind.event_type().and_then(|t| self.handlers.get(&t)),
+                };
+
+                for handler in handlers.into_iter().flatten() {
+                    let permit = semaphore.clone().acquire_owned().await.unwrap();
+                    while let Ok(e) = error_rx.try_recv() {
+                        if let Some(result) = self.handle_error(e) {
+                            break 'outer result;
+                        }
+                    }
+
+                    let fut = handler(event.clone());
+                    let error_tx = error_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = fut.await {
+                            let _ = error_tx.send(e);
+                        }
+                        drop(permit);
+                    });
+                }
+            }
+        };
+
+        // Wait for running handlers
+        let _ = semaphore.acquire_many(self.concurrency as u32).await;
+        let queue_id = queue.id().to_owned();
+        if let Err(e) = queue.unregister().await {
+            log::warn!("failed to delete queue '{}': {}", queue_id, e);
+        }
+        result
+    }
+}
+
+impl Router {
+    /// Returns the result to stop with, if any.
+    fn handle_error(&self, error: HandlerError) -> Option<Result<(), Error>> {
+        match self.error_policy {
+            ErrorPolicy::Log => {
+                log::error!("event handler failed: {}", error);
+                None
+            }
+            ErrorPolicy::Stop => Some(Ok(())),
+            ErrorPolicy::Propagate => Some(Err(Error::new_handler(error))),
+        }
+    }
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoint::Endpoint;
+    use crate::test_util::{
+        mock_method, MockErrorResponse, MockEventsResponse, MockRegisterResponse,
+    };
+    use crate::Client;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn router() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(vec![
+                    json!({ "id": 0, "type": "alert_words", "alert_words": [] }),
+                    json!({
+                        "id": 1, "type": "typing", "op": "start", "message_type": "direct",
+                        "sender": { "user_id": 10, "email": "iago@zulip.com" },
+                        "recipients": []
+                    }),
+                    json!({ "id": 2, "type": "alert_words", "alert_words": ["stop"] }),
+                    json!({ "id": 3, "type": "alert_words", "alert_words": [] }),
+                ])),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+        server
+            .register(
+                mock_method(
+                    "DELETE",
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "msg": "", "result": "success" })),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .expect(1),
+            )
+            .await;
+
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let typing = handled.clone();
+        let alert_words = handled.clone();
+        let router = Router::new()
+            .error_policy(ErrorPolicy::Propagate)
+            .on_typing(move |e| {
+                typing.lock().unwrap().push(e.sender.user_id as i32);
+                async { Ok::<_, Error>(()) }
+            })
+            .on(EventType::AlertWords, move |e| {
+                alert_words.lock().unwrap().push(e.id());
+                async move {
+                    match e.kind() {
+                        EventKind::AlertWords(w) if w.alert_words.is_empty() => Ok(()),
+                        _ => Err("stop"),
+                    }
+                }
+            });
+
+        let event_types: Vec<_> = router.event_types().collect();
+        assert_eq!(event_types, vec![EventType::Typing, EventType::AlertWords]);
+
+        let client = Client::build(server.uri()).init().await?;
+        let error = router.run(client.queue()).await.unwrap_err();
+        assert!(error.is_handler());
+        assert_eq!(handled.lock().unwrap().as_slice(), &[0, 10, 2]);
+
+        // Only the event types with handlers are requested
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone())?;
+        assert!(body.contains("event_types=%5B%22typing%22%2C%22alert_words%22%5D"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn empty_router() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let client = Client::build(server.uri()).init().await?;
+
+        Router::new().run(client.queue()).await?;
+        assert!(server.received_requests().await.unwrap().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resynchronized() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(
+                mock_method(
+                    "POST",
+                    ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                    Endpoint::REGISTER_EVENT_QUEUE,
+                )
+                .expect(2),
+            )
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(400).set_body_json(MockErrorResponse::bad_event_queue()),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(400).set_body_json(MockErrorResponse::auth_failed()),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let resynchronized = Arc::new(Mutex::new(Vec::new()));
+        let queue_ids = resynchronized.clone();
+        let router = Router::new()
+            .on(EventType::AlertWords, |_| async { Ok::<_, Error>(()) })
+            .on_resynchronized(move |response| {
+                queue_ids
+                    .lock()
+                    .unwrap()
+                    .push(response.queue_id().to_owned());
+                async { Ok::<_, Error>(()) }
+            });
+
+        let client = Client::build(server.uri()).init().await?;
+        let error = router
+            .run(client.queue().resilient(true))
+            .await
+            .unwrap_err();
+        assert!(error.is_zulip());
+        assert_eq!(
+            resynchronized.lock().unwrap().as_slice(),
+            &[MockRegisterResponse::QUEUE_ID]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use super::{Dispatcher, Event};
+use crate::Error;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Stream of the events received by a [`Queue`](super::Queue).
+///
+/// Events are long-polled by a background task and buffered in a bounded
+/// channel. Once the buffer is full, the queue is not polled again until the
+/// consumer catches up. The stream ends after yielding an error.
+pub struct EventStream {
+    receiver: mpsc::Receiver<Result<Event, Error>>,
+    task: JoinHandle<()>,
+}
+
+impl EventStream {
+    pub(super) fn new(dispatcher: Dispatcher, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let task = tokio::spawn(forward(dispatcher, sender, |res| res));
+
+        Self { receiver, task }
+    }
+}
+
+/// Polls the events of a queue and sends them one by one, until an error is
+/// sent or the receiver is dropped.
+pub(super) async fn forward<T, F>(mut dispatcher: Dispatcher, sender: mpsc::Sender<T>, map: F)
+where
+    F: Fn(Result<Event, Error>) -> T,
+{
+    loop {
+        match dispatcher.events().await {
+            Ok(events) => {
+                for event in events {
+                    if sender.send(map(Ok(event))).await.is_err() {
+                        // Stream was dropped
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = sender.send(map(Err(e))).await;
+                return;
+            }
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // Do not keep long-polling a queue nobody listens to
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::endpoint::Endpoint;
+    use crate::event::EventKind;
+    use crate::test_util::{
+        mock_method, MockErrorResponse, MockEventsResponse, MockRegisterResponse,
+    };
+    use crate::Client;
+    use futures::StreamExt;
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn event_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(MockRegisterResponse::new()),
+                Endpoint::REGISTER_EVENT_QUEUE,
+            ))
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(200).set_body_json(MockEventsResponse::new(vec![
+                        json!({ "id": 0, "type": "alert_words", "alert_words": ["foo"] }),
+                        json!({ "id": 1, "type": "alert_words", "alert_words": [] }),
+                    ])),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(
+                mock_method(
+                    "GET",
+                    ResponseTemplate::new(200).set_body_json(MockEventsResponse::heartbeat(2)),
+                    Endpoint::EVENTS_QUEUE,
+                )
+                .up_to_n_times(1),
+            )
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(400).set_body_json(MockErrorResponse::bad_event_queue()),
+                Endpoint::EVENTS_QUEUE,
+            ))
+            .await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let stream = client
+            .queue()
+            .register()
+            .await?
+            .into_stream_with_capacity(1);
+        let events: Vec<_> = stream.collect().await;
+
+        // Heartbeats are not yielded and the stream ends after the error
+        assert_eq!(events.len(), 3);
+        let ids: Vec<_> = events[..2]
+            .iter()
+            .map(|e| e.as_ref().map(|e| e.id()).unwrap())
+            .collect();
+        assert_eq!(ids, vec![0, 1]);
+        assert!(matches!(
+            events[0].as_ref().unwrap().kind(),
+            EventKind::AlertWords(_)
+        ));
+        assert!(events[2].as_ref().unwrap_err().is_zulip());
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 mod endpoint;
 mod error;
 pub mod event;
+pub mod message;
+pub mod narrow;
+pub mod realm;
+pub mod state;
+pub mod stream;
+pub mod user;
 
 #[cfg(test)]
 mod test_util;
@@ -8,11 +14,25 @@ mod test_util;
 pub use error::Error;
 
 use endpoint::Endpoint;
-use event::QueueBuilder;
+use event::{Queue, QueueBuilder, QueueCheckpoint};
+use message::flags::{self, FlagOp};
+use message::{drafts, edit, reactions, scheduled};
+use message::{
+    Destination, Draft, EditMessageBuilder, EditScheduledMessageBuilder, Emoji, Flag,
+    GetMessagesBuilder, MessageSnapshot, ScheduleMessageBuilder, ScheduledMessage,
+    SendMessageBuilder,
+};
+use narrow::Narrow;
 use reqwest::Client as HttpClient;
 use reqwest::{IntoUrl, Method, Response, Url};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use stream::{subscriptions, user_topics};
+use stream::{
+    CreateStreamBuilder, GetStreamsBuilder, Stream, SubscribeBuilder, Subscription,
+    SubscriptionProperty, Topic, UnsubscribeBuilder, UpdateStreamBuilder, VisibilityPolicy,
+};
+use user::{settings, GetUsersBuilder, UpdateUserBuilder, User, UserSetting};
 
 #[derive(Debug, Clone)]
 pub struct Client {
@@ -59,6 +79,293 @@ impl Client {
     pub fn queue(&self) -> QueueBuilder {
         QueueBuilder::new(self.clone())
     }
+
+    pub fn send_message<C: Into<String>>(&self, to: Destination, content: C) -> SendMessageBuilder {
+        SendMessageBuilder::new(self.clone(), to, content.into())
+    }
+
+    pub fn get_messages(&self) -> GetMessagesBuilder {
+        GetMessagesBuilder::new(self.clone())
+    }
+
+    pub fn edit_message(&self, message_id: u64) -> EditMessageBuilder {
+        EditMessageBuilder::new(self.clone(), message_id)
+    }
+
+    pub async fn delete_message(&self, message_id: u64) -> Result<(), Error> {
+        edit::delete(self, message_id).await
+    }
+
+    /// Adds a flag to the given messages, returning the IDs of the messages
+    /// that did not have it.
+    pub async fn add_flag<I>(&self, flag: Flag, message_ids: I) -> Result<Vec<u64>, Error>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let message_ids = message_ids.into_iter().collect();
+        flags::update_flag(self, FlagOp::Add, flag, message_ids).await
+    }
+
+    /// Removes a flag from the given messages, returning the IDs of the
+    /// messages that had it.
+    pub async fn remove_flag<I>(&self, flag: Flag, message_ids: I) -> Result<Vec<u64>, Error>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let message_ids = message_ids.into_iter().collect();
+        flags::update_flag(self, FlagOp::Remove, flag, message_ids).await
+    }
+
+    /// Adds a flag to every message of a narrow, returning the number of
+    /// messages that did not have it. Messages are updated in batches of
+    /// 1000, so a failure may leave the narrow partially updated.
+    pub async fn add_flag_in_narrow<N: Into<Narrow>>(
+        &self,
+        flag: Flag,
+        narrow: N,
+    ) -> Result<u64, Error> {
+        flags::update_flag_in_narrow(self, FlagOp::Add, flag, narrow.into()).await
+    }
+
+    /// Like [`Client::add_flag_in_narrow`], but removing the flag.
+    pub async fn remove_flag_in_narrow<N: Into<Narrow>>(
+        &self,
+        flag: Flag,
+        narrow: N,
+    ) -> Result<u64, Error> {
+        flags::update_flag_in_narrow(self, FlagOp::Remove, flag, narrow.into()).await
+    }
+
+    pub async fn mark_all_as_read(&self) -> Result<(), Error> {
+        flags::mark_all_as_read(self).await
+    }
+
+    pub async fn mark_stream_as_read(&self, stream_id: u64) -> Result<(), Error> {
+        flags::mark_stream_as_read(self, stream_id).await
+    }
+
+    pub async fn mark_topic_as_read<T: Into<String>>(
+        &self,
+        stream_id: u64,
+        topic: T,
+    ) -> Result<(), Error> {
+        flags::mark_topic_as_read(self, stream_id, topic.into()).await
+    }
+
+    /// Versions of a message, from the original to the current one.
+    pub async fn message_history(&self, message_id: u64) -> Result<Vec<MessageSnapshot>, Error> {
+        edit::history(self, message_id).await
+    }
+
+    /// Renders Markdown content as the server would render a message,
+    /// returning the HTML.
+    pub async fn render_message<C: Into<String>>(&self, content: C) -> Result<String, Error> {
+        message::render(self, content.into()).await
+    }
+
+    /// Reacts to a message with an emoji, given by name or as an [`Emoji`].
+    pub async fn add_reaction<E: Into<Emoji>>(
+        &self,
+        message_id: u64,
+        emoji: E,
+    ) -> Result<(), Error> {
+        reactions::update_reaction(self, Method::POST, message_id, &emoji.into()).await
+    }
+
+    pub async fn remove_reaction<E: Into<Emoji>>(
+        &self,
+        message_id: u64,
+        emoji: E,
+    ) -> Result<(), Error> {
+        reactions::update_reaction(self, Method::DELETE, message_id, &emoji.into()).await
+    }
+
+    /// Scheduled messages of the user that were not sent yet.
+    pub async fn scheduled_messages(&self) -> Result<Vec<ScheduledMessage>, Error> {
+        scheduled::list(self).await
+    }
+
+    /// Schedules a message to be sent by the server at a UNIX timestamp, in
+    /// seconds.
+    ///
+    /// Scheduled messages can only be sent to streams and users by ID, other
+    /// destinations are rejected with [`UnsupportedDestination`](message::UnsupportedDestination).
+    pub fn schedule_message<C: Into<String>>(
+        &self,
+        to: Destination,
+        content: C,
+        timestamp: i64,
+    ) -> ScheduleMessageBuilder {
+        ScheduleMessageBuilder::new(self.clone(), to, content.into(), timestamp)
+    }
+
+    pub fn edit_scheduled_message(&self, scheduled_message_id: u64) -> EditScheduledMessageBuilder {
+        EditScheduledMessageBuilder::new(self.clone(), scheduled_message_id)
+    }
+
+    pub async fn delete_scheduled_message(&self, scheduled_message_id: u64) -> Result<(), Error> {
+        scheduled::delete(self, scheduled_message_id).await
+    }
+
+    pub async fn drafts(&self) -> Result<Vec<Draft>, Error> {
+        drafts::list(self).await
+    }
+
+    /// Saves new drafts, returning their IDs in the same order.
+    pub async fn create_drafts<I>(&self, drafts: I) -> Result<Vec<u64>, Error>
+    where
+        I: IntoIterator<Item = Draft>,
+    {
+        drafts::create(self, drafts.into_iter().collect()).await
+    }
+
+    /// Replaces the content and recipients of a draft.
+    pub async fn edit_draft(&self, draft_id: u64, draft: &Draft) -> Result<(), Error> {
+        drafts::edit(self, draft_id, draft).await
+    }
+
+    pub async fn delete_draft(&self, draft_id: u64) -> Result<(), Error> {
+        drafts::delete(self, draft_id).await
+    }
+
+    pub fn streams(&self) -> GetStreamsBuilder {
+        GetStreamsBuilder::new(self.clone())
+    }
+
+    pub async fn stream(&self, stream_id: u64) -> Result<Stream, Error> {
+        stream::stream(self, stream_id).await
+    }
+
+    pub async fn stream_id(&self, name: &str) -> Result<u64, Error> {
+        stream::stream_id(self, name).await
+    }
+
+    pub fn create_stream<N: Into<String>>(&self, name: N) -> CreateStreamBuilder {
+        CreateStreamBuilder::new(self.clone(), name.into())
+    }
+
+    pub fn update_stream(&self, stream_id: u64) -> UpdateStreamBuilder {
+        UpdateStreamBuilder::new(self.clone(), stream_id)
+    }
+
+    /// Archives a stream, unsubscribing everyone. Its messages are kept
+    /// according to the retention policy of the realm.
+    pub async fn archive_stream(&self, stream_id: u64) -> Result<(), Error> {
+        stream::archive(self, stream_id).await
+    }
+
+    /// Topics of a stream the user can access, from the most recent.
+    pub async fn stream_topics(&self, stream_id: u64) -> Result<Vec<Topic>, Error> {
+        stream::topics(self, stream_id).await
+    }
+
+    /// Mutes, unmutes or follows a topic for the current user.
+    pub async fn set_topic_visibility<T: Into<String>>(
+        &self,
+        stream_id: u64,
+        topic: T,
+        policy: VisibilityPolicy,
+    ) -> Result<(), Error> {
+        user_topics::set_visibility(self, stream_id, topic.into(), policy).await
+    }
+
+    /// Streams the user is subscribed to, with their subscribers if
+    /// requested.
+    pub async fn subscriptions(
+        &self,
+        include_subscribers: bool,
+    ) -> Result<Vec<Subscription>, Error> {
+        subscriptions::list(self, include_subscribers).await
+    }
+
+    /// Subscribes to streams by name, creating the missing ones.
+    pub fn subscribe<I, S>(&self, streams: I) -> SubscribeBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        SubscribeBuilder::new(self.clone(), streams.into_iter().map(Into::into).collect())
+    }
+
+    /// Unsubscribes from streams by name.
+    pub fn unsubscribe<I, S>(&self, streams: I) -> UnsubscribeBuilder
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        UnsubscribeBuilder::new(self.clone(), streams.into_iter().map(Into::into).collect())
+    }
+
+    /// Updates personal settings of subscriptions, by stream ID.
+    pub async fn update_subscriptions<I>(&self, changes: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (u64, SubscriptionProperty)>,
+    {
+        subscriptions::update(self, changes).await
+    }
+
+    /// Profile of the current user.
+    pub async fn me(&self) -> Result<User, Error> {
+        settings::me(self).await
+    }
+
+    /// Updates settings of the current user. Changes are also sent to every
+    /// queue of the user as `user_settings` events.
+    pub async fn update_settings<I>(&self, settings: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = UserSetting>,
+    {
+        settings::update(self, settings.into_iter().collect()).await
+    }
+
+    pub fn users(&self) -> GetUsersBuilder {
+        GetUsersBuilder::new(self.clone())
+    }
+
+    pub async fn user(&self, user_id: u64) -> Result<User, Error> {
+        user::user(self, user_id).await
+    }
+
+    pub async fn user_by_email(&self, email: &str) -> Result<User, Error> {
+        user::user_by_email(self, email).await
+    }
+
+    /// Creates a user, returning its ID. Requires the `can_create_users`
+    /// permission.
+    pub async fn create_user<E, P, N>(
+        &self,
+        email: E,
+        password: P,
+        full_name: N,
+    ) -> Result<u64, Error>
+    where
+        E: Into<String>,
+        P: Into<String>,
+        N: Into<String>,
+    {
+        user::create(self, email.into(), password.into(), full_name.into()).await
+    }
+
+    /// Deactivates a user, logging them out and removing them from the
+    /// realm. Their messages are kept.
+    pub async fn deactivate_user(&self, user_id: u64) -> Result<(), Error> {
+        user::deactivate(self, user_id).await
+    }
+
+    pub async fn reactivate_user(&self, user_id: u64) -> Result<(), Error> {
+        user::reactivate(self, user_id).await
+    }
+
+    pub fn update_user(&self, user_id: u64) -> UpdateUserBuilder {
+        UpdateUserBuilder::new(self.clone(), user_id)
+    }
+
+    /// Continues polling the queue saved in a checkpoint. If it expired on the
+    /// server, it is registered again on the first poll, which returns an
+    /// [`EventKind::Resynchronized`](event::EventKind::Resynchronized) event.
+    pub fn resume_queue(&self, checkpoint: QueueCheckpoint) -> Queue {
+        Queue::resume(self.clone(), checkpoint)
+    }
 }
 
 #[derive(Debug)]
@@ -81,16 +388,12 @@ impl ClientInner {
         self.credentials.replace(credentials);
     }
 
-    async fn deserialize<R>(response: Response) -> R
+    async fn deserialize<R>(response: Response) -> Result<R, Error>
     where
         R: serde::de::DeserializeOwned,
     {
-        match response.json().await {
-            Ok(data) => data,
-            // Since this function is internally called, we should know the type
-            // of data we expect, otherwise there is implementation issue
-            Err(e) => panic!("{}. This should not happen, report the issue", e),
-        }
+        let body = response.bytes().await?;
+        serde_json::from_slice(&body).map_err(Error::new_decode)
     }
 
     async fn send<T, R>(&self, method: Method, endpoint: &str, params: &T) -> Result<R, Error>
@@ -102,7 +405,7 @@ impl ClientInner {
 
         if res.status().is_client_error() {
             // Create error from body
-            Err(Error::new_zulip(Self::deserialize(res).await))
+            Err(Error::new_zulip(Self::deserialize(res).await?))
         } else if res.status().is_server_error() {
             // Create error from status
             res.error_for_status()?;
@@ -112,7 +415,7 @@ impl ClientInner {
             unimplemented!();
         } else {
             // Successful response
-            Ok(Self::deserialize(res).await)
+            Self::deserialize(res).await
         }
     }
 
@@ -245,6 +548,14 @@ impl Credentials {
     }
 }
 
+/// Parameters of requests without any.
+#[derive(Serialize)]
+pub(crate) struct EmptyRequest {}
+
+/// Response of requests returning no data.
+#[derive(Deserialize)]
+pub(crate) struct EmptyResponse {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,6 +641,22 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn decode_fail() -> Result<(), Box<dyn std::error::Error>> {
+        let template = ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "result": "success",
+        }));
+        let server = mock_server(template, Endpoint::FETCH_API_KEY).await;
+        let error = Client::build(server.uri())
+            .with_credentials(MockCredentials::USERNAME, Some(MockCredentials::PASSWORD))
+            .init()
+            .await
+            .expect_err("Client initialization should return an error");
+
+        assert!(error.is_decode());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn base_uri() -> Result<(), Error> {
         const CANONICAL_URI: &str = "https://hello.zulipchat.com";
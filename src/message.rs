@@ -0,0 +1,384 @@
+use crate::endpoint::Endpoint;
+use crate::{Client, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+mod content;
+pub(crate) mod drafts;
+pub(crate) mod edit;
+mod fetch;
+pub(crate) mod flags;
+pub(crate) mod reactions;
+pub(crate) mod scheduled;
+
+pub use content::{CodeBlock, Content, Link, Mention};
+pub use drafts::{Draft, DraftType};
+pub use edit::{EditMessageBuilder, MessageSnapshot, PropagateMode};
+pub use fetch::{Anchor, Direction, GetMessagesBuilder, GetMessagesResponse, MessagePages};
+pub use flags::Flag;
+pub use reactions::{Emoji, ReactionType};
+pub use scheduled::{
+    EditScheduledMessageBuilder, ScheduleMessageBuilder, ScheduledMessage, ScheduledRecipient,
+    UnsupportedDestination,
+};
+
+/// A message as sent by the server, either in a `message` event or in a
+/// message query.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Message {
+    pub id: u64,
+    pub sender_id: u64,
+    pub sender_email: String,
+    pub sender_full_name: String,
+    #[serde(default)]
+    pub sender_realm_str: String,
+    pub avatar_url: Option<String>,
+    pub content: String,
+    pub content_type: String,
+    #[serde(rename = "type")]
+    pub kind: MessageType,
+    pub display_recipient: DisplayRecipient,
+    pub stream_id: Option<u64>,
+    pub recipient_id: u64,
+    /// Topic of a stream message. Empty for direct messages.
+    #[serde(rename = "subject")]
+    pub topic: String,
+    pub timestamp: i64,
+    pub last_edit_timestamp: Option<i64>,
+    #[serde(default)]
+    pub is_me_message: bool,
+    #[serde(default)]
+    pub client: String,
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+impl Message {
+    /// Parses the content of the message, unless it was fetched without
+    /// rendering it, i.e. with `apply_markdown` set to false.
+    pub fn parse_content(&self) -> Option<Content> {
+        match self.content_type.as_str() {
+            "text/html" => Some(Content::parse(&self.content)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    #[serde(rename = "stream")]
+    Stream,
+    #[serde(rename = "private", alias = "direct")]
+    Direct,
+}
+
+/// Recipient of a message: the stream name for stream messages or the list
+/// of users for direct messages.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum DisplayRecipient {
+    Stream(String),
+    Users(Vec<Recipient>),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Recipient {
+    pub id: u64,
+    pub email: String,
+    pub full_name: String,
+    #[serde(default)]
+    pub is_mirror_dummy: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Reaction {
+    pub emoji_name: String,
+    pub emoji_code: String,
+    pub reaction_type: ReactionType,
+    pub user_id: u64,
+}
+
+impl Reaction {
+    /// Emoji of the reaction, e.g. to remove it.
+    pub fn emoji(&self) -> Emoji {
+        Emoji::new(self.emoji_name.clone())
+            .with_code(self.emoji_code.clone(), self.reaction_type.clone())
+    }
+}
+
+/// Where to send a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// Topic of a stream, by stream name.
+    Stream { name: String, topic: String },
+    /// Topic of a stream, by stream ID.
+    StreamId { id: u64, topic: String },
+    /// Direct message to one or more users, by user ID.
+    UserIds(Vec<u64>),
+    /// Direct message to one or more users, by email.
+    UserEmails(Vec<String>),
+}
+
+impl Destination {
+    pub fn stream<S, T>(name: S, topic: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        Self::Stream {
+            name: name.into(),
+            topic: topic.into(),
+        }
+    }
+
+    pub fn stream_id<T: Into<String>>(id: u64, topic: T) -> Self {
+        Self::StreamId {
+            id,
+            topic: topic.into(),
+        }
+    }
+
+    pub fn user_ids<I: IntoIterator<Item = u64>>(ids: I) -> Self {
+        Self::UserIds(ids.into_iter().collect())
+    }
+
+    pub fn user_emails<I, E>(emails: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        Self::UserEmails(emails.into_iter().map(Into::into).collect())
+    }
+
+    /// Destination to reply to a message: its topic for stream messages, or
+    /// the same conversation for direct messages.
+    pub fn reply_to(message: &Message) -> Self {
+        match (&message.display_recipient, message.stream_id) {
+            (DisplayRecipient::Users(users), _) => Self::user_ids(users.iter().map(|u| u.id)),
+            (DisplayRecipient::Stream(name), None) => Self::stream(name, &message.topic),
+            (DisplayRecipient::Stream(_), Some(id)) => Self::stream_id(id, &message.topic),
+        }
+    }
+
+    fn kind(&self) -> MessageType {
+        match self {
+            Self::Stream { .. } | Self::StreamId { .. } => MessageType::Stream,
+            Self::UserIds(_) | Self::UserEmails(_) => MessageType::Direct,
+        }
+    }
+
+    fn topic(&self) -> Option<&str> {
+        match self {
+            Self::Stream { topic, .. } | Self::StreamId { topic, .. } => Some(topic),
+            Self::UserIds(_) | Self::UserEmails(_) => None,
+        }
+    }
+
+    /// Value of the `to` parameter: the stream name or ID, or the users as
+    /// a json list.
+    fn to(&self) -> String {
+        match self {
+            Self::Stream { name, .. } => name.clone(),
+            Self::StreamId { id, .. } => id.to_string(),
+            Self::UserIds(ids) => serde_json::to_string(ids).unwrap(),
+            Self::UserEmails(emails) => serde_json::to_string(emails).unwrap(),
+        }
+    }
+}
+
+/// Builder of a message to send, created with
+/// [`Client::send_message`](crate::Client::send_message).
+pub struct SendMessageBuilder {
+    request: SendMessageRequest,
+    client: Client,
+}
+
+impl SendMessageBuilder {
+    pub(crate) fn new(client: Client, to: Destination, content: String) -> Self {
+        Self {
+            request: SendMessageRequest {
+                kind: to.kind(),
+                to: to.to(),
+                topic: to.topic().map(str::to_owned),
+                content,
+                queue_id: None,
+                local_id: None,
+                read_by_sender: None,
+            },
+            client,
+        }
+    }
+
+    /// Identifies the message in the `message` event of the given queue, for
+    /// local echo.
+    pub fn local_id<Q, L>(mut self, queue_id: Q, local_id: L) -> Self
+    where
+        Q: Into<String>,
+        L: Into<String>,
+    {
+        self.request.queue_id.replace(queue_id.into());
+        self.request.local_id.replace(local_id.into());
+        self
+    }
+
+    /// Whether the message is marked as read for the sender. The server
+    /// decides by default, based on the client.
+    pub fn read_by_sender(mut self, value: bool) -> Self {
+        self.request.read_by_sender.replace(value);
+        self
+    }
+
+    /// Sends the message, returning its ID.
+    ///
+    /// Missing streams and forbidden wildcard mentions fail with a specific
+    /// error code. Other failures, such as unknown or deactivated recipients,
+    /// or a missing topic, are reported by the server as a `BAD_REQUEST` with
+    /// a description only.
+    pub async fn send(self) -> Result<u64, Error> {
+        let res: SendMessageResponse = self
+            .client
+            .send(Method::POST, Endpoint::MESSAGES, &self.request)
+            .await?;
+        Ok(res.id)
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct SendMessageRequest {
+    #[serde(rename = "type")]
+    kind: MessageType,
+    to: String,
+    topic: Option<String>,
+    content: String,
+    queue_id: Option<String>,
+    local_id: Option<String>,
+    read_by_sender: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct SendMessageResponse {
+    id: u64,
+}
+
+pub(crate) async fn render(client: &Client, content: String) -> Result<String, Error> {
+    let request = RenderMessageRequest { content };
+    let res: RenderMessageResponse = client
+        .send(Method::POST, Endpoint::RENDER_MESSAGE, &request)
+        .await?;
+    Ok(res.rendered)
+}
+
+#[derive(Serialize, Debug)]
+struct RenderMessageRequest {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct RenderMessageResponse {
+    rendered: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_server, MockErrorResponse};
+    use serde_json::json;
+    use wiremock::ResponseTemplate;
+
+    fn send_response() -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .set_body_json(json!({ "id": 42, "msg": "", "result": "success" }))
+    }
+
+    #[tokio::test]
+    async fn send_message() -> Result<(), Box<dyn std::error::Error>> {
+        let server = mock_server(send_response(), Endpoint::MESSAGES).await;
+        let client = Client::build(server.uri()).init().await?;
+        let id = client
+            .send_message(Destination::stream("Denmark", "Castle"), "Hello")
+            .send()
+            .await?;
+
+        assert_eq!(id, 42);
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "type=stream&to=Denmark&topic=Castle&content=Hello"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_direct_message() -> Result<(), Box<dyn std::error::Error>> {
+        let server = mock_server(send_response(), Endpoint::MESSAGES).await;
+        let client = Client::build(server.uri()).init().await?;
+        client
+            .send_message(
+                Destination::user_emails(["iago@zulip.com", "othello@zulip.com"]),
+                "Hi",
+            )
+            .read_by_sender(true)
+            .send()
+            .await?;
+
+        // Recipients are sent as a json list
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "type=private&to=%5B%22iago%40zulip.com%22%2C%22othello%40zulip.com%22%5D\
+             &content=Hi&read_by_sender=true"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_to_missing_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let template =
+            ResponseTemplate::new(400).set_body_json(MockErrorResponse::stream_not_found());
+        let server = mock_server(template, Endpoint::MESSAGES).await;
+        let client = Client::build(server.uri()).init().await?;
+        let error = client
+            .send_message(Destination::stream("Nowhere", "Castle"), "Hello")
+            .send()
+            .await
+            .unwrap_err();
+
+        match error.kind() {
+            crate::error::ErrorKind::Zulip(e) => assert!(e.is_stream_not_found()),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn render_message() -> Result<(), Box<dyn std::error::Error>> {
+        let template = ResponseTemplate::new(200).set_body_json(json!({
+            "rendered": "<p><strong>foo</strong></p>", "msg": "", "result": "success"
+        }));
+        let server = mock_server(template, Endpoint::RENDER_MESSAGE).await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let rendered = client.render_message("**foo**").await?;
+        assert_eq!(rendered, "<p><strong>foo</strong></p>");
+        assert_eq!(Content::parse(&rendered).text, "foo");
+        Ok(())
+    }
+
+    #[test]
+    fn reply_to() {
+        let message: Message = serde_json::from_value(json!({
+            "id": 1, "sender_id": 10, "sender_email": "iago@zulip.com",
+            "sender_full_name": "Iago", "content": "Hi", "content_type": "text/html",
+            "type": "private", "recipient_id": 3, "subject": "", "timestamp": 0,
+            "display_recipient": [
+                { "id": 10, "email": "iago@zulip.com", "full_name": "Iago" },
+                { "id": 11, "email": "othello@zulip.com", "full_name": "Othello" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            Destination::reply_to(&message),
+            Destination::UserIds(vec![10, 11])
+        );
+    }
+}
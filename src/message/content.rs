@@ -0,0 +1,529 @@
+use super::{Emoji, ReactionType};
+
+/// Plain text and notable elements of the HTML content of a message, as
+/// rendered by the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Content {
+    /// Text of the message, with a line per paragraph and unicode emoji
+    /// instead of their names.
+    pub text: String,
+    pub mentions: Vec<Mention>,
+    pub links: Vec<Link>,
+    pub emoji: Vec<Emoji>,
+    pub code_blocks: Vec<CodeBlock>,
+    /// Text of each quote, including nested ones.
+    pub quotes: Vec<String>,
+}
+
+impl Content {
+    /// Extracts the content of a message rendered by the server.
+    ///
+    /// Only the markup produced by the server is understood; the result for
+    /// arbitrary HTML is unspecified.
+    pub fn parse(html: &str) -> Self {
+        let mut parser = Parser::default();
+        for token in Tokenizer::new(html) {
+            match token {
+                Token::Open(tag) => parser.open(tag),
+                Token::Close(name) => parser.close(&name),
+                Token::Text(text) => parser.text(&decode_entities(text)),
+            }
+        }
+        parser.finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mention {
+    User {
+        user_id: u64,
+        name: String,
+        /// Silent mentions do not notify the user.
+        silent: bool,
+    },
+    Group {
+        group_id: u64,
+        name: String,
+        silent: bool,
+    },
+    /// Mention of everyone in the conversation, e.g. `@all` or `@topic`.
+    Wildcard { name: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Target of the link, relative to the realm for links to streams,
+    /// topics and messages.
+    pub url: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+}
+
+/// Elements whose text is being captured.
+enum Capture {
+    None,
+    Mention(Tag),
+    Link(String),
+    Emoji(Option<String>),
+    Code(Option<String>),
+    Quote,
+}
+
+struct Frame {
+    name: String,
+    capture: Capture,
+    text: String,
+}
+
+#[derive(Default)]
+struct Parser {
+    stack: Vec<Frame>,
+    content: Content,
+    /// Language of the code block being opened.
+    code_language: Option<String>,
+}
+
+impl Parser {
+    fn open(&mut self, tag: Tag) {
+        if is_block(&tag.name) {
+            self.new_line();
+        }
+
+        match tag.name.as_str() {
+            "br" => return self.content.text.push('\n'),
+            "img" if tag.has_class("emoji") => {
+                if let Some(alt) = tag.attr("alt") {
+                    self.content.emoji.push(Emoji::new(alt.trim_matches(':')));
+                    self.text(alt);
+                }
+                return;
+            }
+            name if is_void(name) => return,
+            _ => {}
+        }
+
+        let capture = match tag.name.as_str() {
+            "span"
+                if tag.has_class("user-mention")
+                    || tag.has_class("user-group-mention")
+                    || tag.has_class("topic-mention") =>
+            {
+                Capture::Mention(tag.clone())
+            }
+            "span" if tag.has_class("emoji") => {
+                let code = tag
+                    .classes()
+                    .find_map(|c| c.strip_prefix("emoji-"))
+                    .map(str::to_owned);
+                Capture::Emoji(code)
+            }
+            "a" => Capture::Link(tag.attr("href").unwrap_or_default().to_owned()),
+            "div" if tag.has_class("codehilite") => {
+                self.code_language = tag.attr("data-code-language").map(str::to_owned);
+                Capture::None
+            }
+            "pre" => Capture::Code(self.code_language.take()),
+            "blockquote" => Capture::Quote,
+            _ => Capture::None,
+        };
+        self.stack.push(Frame {
+            name: tag.name,
+            capture,
+            text: String::new(),
+        });
+    }
+
+    fn close(&mut self, name: &str) {
+        if !self.stack.iter().any(|f| f.name == name) {
+            return;
+        }
+        // Close unterminated elements as well
+        while let Some(frame) = self.stack.pop() {
+            let matched = frame.name == name;
+            self.finish_frame(frame);
+            if matched {
+                break;
+            }
+        }
+    }
+
+    fn finish_frame(&mut self, frame: Frame) {
+        if is_block(&frame.name) {
+            self.new_line();
+        }
+
+        let text = frame.text.trim().to_owned();
+        match frame.capture {
+            Capture::None => {}
+            Capture::Mention(tag) => {
+                if let Some(mention) = mention(&tag, text) {
+                    self.content.mentions.push(mention);
+                }
+            }
+            Capture::Link(url) => self.content.links.push(Link { url, text }),
+            Capture::Emoji(code) => {
+                let name = text.trim_matches(':');
+                let mut emoji = Emoji::new(name);
+                match code.as_deref().and_then(unicode_emoji) {
+                    Some(unicode) => {
+                        emoji = emoji.with_code(code.unwrap(), ReactionType::UnicodeEmoji);
+                        self.push_text(&unicode);
+                    }
+                    None => self.push_text(&text),
+                }
+                self.content.emoji.push(emoji);
+            }
+            Capture::Code(language) => self.content.code_blocks.push(CodeBlock {
+                language,
+                code: frame.text.trim_end_matches('\n').to_owned(),
+            }),
+            Capture::Quote => self.content.quotes.push(text),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        // The name of unicode emoji is replaced when the element is closed
+        let in_emoji = matches!(
+            self.stack.last(),
+            Some(Frame {
+                capture: Capture::Emoji(_),
+                ..
+            })
+        );
+        if in_emoji {
+            self.stack.last_mut().unwrap().text.push_str(text);
+        } else {
+            self.push_text(text);
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let preformatted = self.stack.iter().any(|f| f.name == "pre");
+        let text = if preformatted {
+            text.to_owned()
+        } else {
+            text.replace('\n', " ")
+        };
+
+        for frame in &mut self.stack {
+            if !matches!(frame.capture, Capture::None) {
+                frame.text.push_str(&text);
+            }
+        }
+
+        let at_line_start = self.content.text.is_empty() || self.content.text.ends_with('\n');
+        if at_line_start && !preformatted {
+            self.content.text.push_str(text.trim_start());
+        } else {
+            self.content.text.push_str(&text);
+        }
+    }
+
+    fn new_line(&mut self) {
+        if !self.content.text.is_empty() && !self.content.text.ends_with('\n') {
+            self.content.text.push('\n');
+        }
+        for frame in &mut self.stack {
+            if matches!(frame.capture, Capture::Quote) && !frame.text.ends_with('\n') {
+                frame.text.push('\n');
+            }
+        }
+    }
+
+    fn finish(mut self) -> Content {
+        while let Some(frame) = self.stack.pop() {
+            self.finish_frame(frame);
+        }
+
+        let text: Vec<_> = self.content.text.lines().map(str::trim_end).collect();
+        self.content.text = text.join("\n").trim().to_owned();
+        self.content
+    }
+}
+
+fn mention(tag: &Tag, text: String) -> Option<Mention> {
+    let silent = tag.has_class("silent");
+    let name = text.trim_start_matches('@').to_owned();
+
+    if tag.has_class("user-group-mention") {
+        let group_id = tag.attr("data-user-group-id")?.parse().ok()?;
+        return Some(Mention::Group {
+            group_id,
+            name,
+            silent,
+        });
+    }
+    match tag.attr("data-user-id") {
+        Some(id) if id != "*" => Some(Mention::User {
+            user_id: id.parse().ok()?,
+            name,
+            silent,
+        }),
+        _ => Some(Mention::Wildcard { name }),
+    }
+}
+
+/// Converts the code of a unicode emoji, e.g. `1f44d` or `1f468-200d-1f4bb`,
+/// to its characters.
+fn unicode_emoji(code: &str) -> Option<String> {
+    code.split('-')
+        .map(|c| u32::from_str_radix(c, 16).ok().and_then(char::from_u32))
+        .collect()
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "blockquote"
+            | "pre"
+            | "ul"
+            | "ol"
+            | "li"
+            | "table"
+            | "tr"
+            | "hr"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+    )
+}
+
+fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "input" | "meta" | "link" | "wbr" | "source"
+    )
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => name.strip_prefix('#').and_then(|n| n.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[derive(Debug, Clone, Default)]
+struct Tag {
+    name: String,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn classes(&self) -> impl Iterator<Item = &str> {
+        self.attr("class").unwrap_or_default().split_whitespace()
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.classes().any(|c| c == class)
+    }
+}
+
+enum Token<'a> {
+    Open(Tag),
+    Close(String),
+    Text(&'a str),
+}
+
+/// Splits HTML into tags and text. Comments and declarations are skipped.
+struct Tokenizer<'a> {
+    html: &'a str,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(html: &'a str) -> Self {
+        Self { html }
+    }
+
+    fn parse_tag(source: &str) -> Tag {
+        let source = source.trim_end_matches('/');
+        let name_end = source
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(source.len());
+        let mut tag = Tag {
+            name: source[..name_end].to_ascii_lowercase(),
+            attrs: Vec::new(),
+        };
+
+        let mut rest = source[name_end..].trim_start();
+        while !rest.is_empty() {
+            let name_end = rest
+                .find(|c: char| c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let name = rest[..name_end].to_ascii_lowercase();
+            rest = rest[name_end..].trim_start();
+
+            let mut value = String::new();
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                let (raw, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = after[1..].find(quote).map_or(after.len(), |i| i + 1);
+                        (&after[1..end], after.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                value = decode_entities(raw);
+                rest = remaining.trim_start();
+            }
+            if !name.is_empty() {
+                tag.attrs.push((name, value));
+            }
+        }
+        tag
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.html.is_empty() {
+                return None;
+            }
+
+            if !self.html.starts_with('<') {
+                let end = self.html.find('<').unwrap_or(self.html.len());
+                let (text, rest) = self.html.split_at(end);
+                self.html = rest;
+                return Some(Token::Text(text));
+            }
+
+            if let Some(comment) = self.html.strip_prefix("<!--") {
+                let end = comment.find("-->").map_or(comment.len(), |i| i + 3);
+                self.html = &comment[end..];
+                continue;
+            }
+
+            let end = self.html.find('>').unwrap_or(self.html.len());
+            let source = &self.html[1..end];
+            self.html = self.html.get(end + 1..).unwrap_or_default();
+
+            if let Some(name) = source.strip_prefix('/') {
+                return Some(Token::Close(name.trim().to_ascii_lowercase()));
+            } else if !source.starts_with('!') && !source.starts_with('?') {
+                return Some(Token::Open(Self::parse_tag(source)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_content() {
+        let html = concat!(
+            r#"<p>Hi <span class="user-mention" data-user-id="10">@Iago</span>, "#,
+            r#"<span class="user-mention silent" data-user-id="11">Othello</span> and "#,
+            r#"<span class="user-group-mention" data-user-group-id="5">@hamlet</span> "#,
+            r#"<span aria-label="thumbs up" class="emoji emoji-1f44d" role="img" "#,
+            r#"title="thumbs up">:thumbs_up:</span></p>"#,
+            "\n<blockquote>\n<p>See <a href=\"https://zulip.com\">Zulip &amp; co</a></p>\n</blockquote>\n",
+            r#"<div class="codehilite" data-code-language="Rust"><pre><span></span><code>"#,
+            "<span class=\"k\">fn</span> main() {}\n</code></pre></div>\n",
+            r#"<p><img alt=":green_tick:" class="emoji" src="/emoji/1.png" title="green tick"> "#,
+            r#"<span class="user-mention" data-user-id="*">@all</span></p>"#,
+        );
+        let content = Content::parse(html);
+
+        assert_eq!(
+            content.text,
+            "Hi @Iago, Othello and @hamlet 👍\nSee Zulip & co\nfn main() {}\n:green_tick: @all"
+        );
+        assert_eq!(
+            content.mentions,
+            vec![
+                Mention::User {
+                    user_id: 10,
+                    name: "Iago".into(),
+                    silent: false
+                },
+                Mention::User {
+                    user_id: 11,
+                    name: "Othello".into(),
+                    silent: true
+                },
+                Mention::Group {
+                    group_id: 5,
+                    name: "hamlet".into(),
+                    silent: false
+                },
+                Mention::Wildcard { name: "all".into() },
+            ]
+        );
+        assert_eq!(
+            content.emoji,
+            vec![
+                Emoji::new("thumbs_up").with_code("1f44d", ReactionType::UnicodeEmoji),
+                Emoji::new("green_tick"),
+            ]
+        );
+        assert_eq!(
+            content.links,
+            vec![Link {
+                url: "https://zulip.com".into(),
+                text: "Zulip & co".into()
+            }]
+        );
+        assert_eq!(content.quotes, vec!["See Zulip & co".to_owned()]);
+        assert_eq!(
+            content.code_blocks,
+            vec![CodeBlock {
+                language: Some("Rust".into()),
+                code: "fn main() {}".into()
+            }]
+        );
+    }
+}
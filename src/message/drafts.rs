@@ -0,0 +1,214 @@
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Unsent message saved by the user, synchronized across their clients.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Draft {
+    /// Assigned by the server when the draft is created.
+    #[serde(skip_serializing)]
+    pub id: Option<u64>,
+    #[serde(rename = "type")]
+    pub kind: DraftType,
+    /// ID of the stream, or of the users of a direct message.
+    pub to: Vec<u64>,
+    /// Topic of a stream message. Empty for direct messages.
+    #[serde(default)]
+    pub topic: String,
+    pub content: String,
+    /// UNIX timestamp, in seconds, of the last edit. Defaults to the time
+    /// the draft is saved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+impl Draft {
+    pub fn stream<T, C>(stream_id: u64, topic: T, content: C) -> Self
+    where
+        T: Into<String>,
+        C: Into<String>,
+    {
+        Self {
+            id: None,
+            kind: DraftType::Stream,
+            to: vec![stream_id],
+            topic: topic.into(),
+            content: content.into(),
+            timestamp: None,
+        }
+    }
+
+    pub fn direct<I, C>(user_ids: I, content: C) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+        C: Into<String>,
+    {
+        Self {
+            id: None,
+            kind: DraftType::Direct,
+            to: user_ids.into_iter().collect(),
+            topic: String::new(),
+            content: content.into(),
+            timestamp: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DraftType {
+    Stream,
+    Direct,
+    /// Draft without recipient yet.
+    Unaddressed,
+    /// Type added by a newer server, kept as sent to send it back.
+    Other(String),
+}
+
+impl DraftType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Stream => "stream",
+            Self::Direct => "private",
+            Self::Unaddressed => "",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl From<String> for DraftType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "stream" => Self::Stream,
+            "private" | "direct" => Self::Direct,
+            "" => Self::Unaddressed,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+impl Serialize for DraftType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for DraftType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+pub(crate) async fn list(client: &Client) -> Result<Vec<Draft>, Error> {
+    let res: DraftsResponse = client
+        .send(Method::GET, Endpoint::DRAFTS, &EmptyRequest {})
+        .await?;
+    Ok(res.drafts)
+}
+
+pub(crate) async fn create(client: &Client, drafts: Vec<Draft>) -> Result<Vec<u64>, Error> {
+    let request = CreateDraftsRequest { drafts };
+    let res: CreateDraftsResponse = client
+        .send(Method::POST, Endpoint::DRAFTS, &request)
+        .await?;
+    Ok(res.ids)
+}
+
+pub(crate) async fn edit(client: &Client, draft_id: u64, draft: &Draft) -> Result<(), Error> {
+    let request = EditDraftRequest { draft };
+    let _: EmptyResponse = client
+        .send(Method::PATCH, &endpoint(draft_id), &request)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn delete(client: &Client, draft_id: u64) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(Method::DELETE, &endpoint(draft_id), &EmptyRequest {})
+        .await?;
+    Ok(())
+}
+
+fn endpoint(draft_id: u64) -> String {
+    format!("{}/{}", Endpoint::DRAFTS, draft_id)
+}
+
+#[derive(Serialize)]
+struct CreateDraftsRequest {
+    #[serde(serialize_with = "crate::serialize")]
+    drafts: Vec<Draft>,
+}
+
+#[derive(Serialize)]
+struct EditDraftRequest<'a> {
+    #[serde(serialize_with = "crate::serialize")]
+    draft: &'a Draft,
+}
+
+#[derive(Deserialize)]
+struct DraftsResponse {
+    drafts: Vec<Draft>,
+}
+
+#[derive(Deserialize)]
+struct CreateDraftsResponse {
+    ids: Vec<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn drafts() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "count": 2,
+                    "drafts": [{
+                        "id": 17, "type": "", "to": [], "topic": "",
+                        "content": "Sketch", "timestamp": 1595479019
+                    }, {
+                        "id": 16, "type": "channel", "to": [1], "topic": "Castle",
+                        "content": "Hello", "timestamp": 1595479018
+                    }],
+                    "msg": "", "result": "success"
+                })),
+                Endpoint::DRAFTS,
+            ))
+            .await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "ids": [18], "msg": "", "result": "success" })),
+                Endpoint::DRAFTS,
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let drafts = client.drafts().await?;
+        assert_eq!(drafts[0].id, Some(17));
+        assert_eq!(drafts[0].kind, DraftType::Unaddressed);
+        assert_eq!(drafts[1].kind, DraftType::Other("channel".into()));
+        assert_eq!(serde_json::to_value(&drafts[1])?["type"], json!("channel"));
+
+        let ids = client
+            .create_drafts([Draft::stream(1, "Castle", "Hi")])
+            .await?;
+        assert_eq!(ids, vec![18]);
+        let body = body_as_string(&server).await?.unwrap();
+        assert_eq!(
+            body,
+            "drafts=%5B%7B%22type%22%3A%22stream%22%2C%22to%22%3A%5B1%5D%2C\
+             %22topic%22%3A%22Castle%22%2C%22content%22%3A%22Hi%22%7D%5D"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Which messages of a topic are edited along with a message when changing
+/// its topic or stream.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PropagateMode {
+    /// Only the message itself.
+    ChangeOne,
+    /// The message and the following ones.
+    ChangeLater,
+    /// Every message of the topic.
+    ChangeAll,
+}
+
+/// Builder of a message edit, created with
+/// [`Client::edit_message`](crate::Client::edit_message).
+pub struct EditMessageBuilder {
+    message_id: u64,
+    request: EditMessageRequest,
+    client: Client,
+}
+
+impl EditMessageBuilder {
+    pub(crate) fn new(client: Client, message_id: u64) -> Self {
+        Self {
+            message_id,
+            request: EditMessageRequest::default(),
+            client,
+        }
+    }
+
+    pub fn content<C: Into<String>>(mut self, content: C) -> Self {
+        self.request.content.replace(content.into());
+        self
+    }
+
+    pub fn topic<T: Into<String>>(mut self, topic: T) -> Self {
+        self.request.topic.replace(topic.into());
+        self
+    }
+
+    /// Moves the message to another stream.
+    pub fn stream_id(mut self, stream_id: u64) -> Self {
+        self.request.stream_id.replace(stream_id);
+        self
+    }
+
+    /// Defaults to [`PropagateMode::ChangeOne`].
+    pub fn propagate_mode(mut self, mode: PropagateMode) -> Self {
+        self.request.propagate_mode.replace(mode);
+        self
+    }
+
+    /// Whether to notify the topic messages were moved from.
+    pub fn notify_old_thread(mut self, value: bool) -> Self {
+        self.request.send_notification_to_old_thread.replace(value);
+        self
+    }
+
+    /// Whether to notify the topic messages were moved to.
+    pub fn notify_new_thread(mut self, value: bool) -> Self {
+        self.request.send_notification_to_new_thread.replace(value);
+        self
+    }
+
+    pub async fn send(self) -> Result<(), Error> {
+        let _: EmptyResponse = self
+            .client
+            .send(Method::PATCH, &endpoint(self.message_id), &self.request)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Version of a message, as returned by
+/// [`Client::message_history`](crate::Client::message_history). The first
+/// snapshot is the original message and each of the next ones an edit.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MessageSnapshot {
+    pub topic: String,
+    /// Topic before the edit, if it was changed.
+    pub prev_topic: Option<String>,
+    /// ID of the stream after the edit, if it was moved.
+    pub stream: Option<u64>,
+    pub prev_stream: Option<u64>,
+    pub content: String,
+    pub rendered_content: String,
+    /// Content before the edit, if it was changed.
+    pub prev_content: Option<String>,
+    pub prev_rendered_content: Option<String>,
+    /// HTML diff between the previous and the new rendered content.
+    pub content_html_diff: Option<String>,
+    /// ID of the user who sent or edited the message.
+    pub user_id: Option<u64>,
+    pub timestamp: i64,
+}
+
+pub(crate) async fn delete(client: &Client, message_id: u64) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(Method::DELETE, &endpoint(message_id), &EmptyRequest {})
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn history(
+    client: &Client,
+    message_id: u64,
+) -> Result<Vec<MessageSnapshot>, Error> {
+    let endpoint = format!("{}/history", endpoint(message_id));
+    let res: MessageHistoryResponse = client
+        .send(Method::GET, &endpoint, &EmptyRequest {})
+        .await?;
+    Ok(res.message_history)
+}
+
+fn endpoint(message_id: u64) -> String {
+    format!("{}/{}", Endpoint::MESSAGES, message_id)
+}
+
+#[derive(Deserialize)]
+struct MessageHistoryResponse {
+    message_history: Vec<MessageSnapshot>,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct EditMessageRequest {
+    content: Option<String>,
+    topic: Option<String>,
+    stream_id: Option<u64>,
+    propagate_mode: Option<PropagateMode>,
+    send_notification_to_old_thread: Option<bool>,
+    send_notification_to_new_thread: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    fn success() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }))
+    }
+
+    #[tokio::test]
+    async fn edit_message() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method("PATCH", success(), "messages/42").expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+        client
+            .edit_message(42)
+            .topic("Castle")
+            .stream_id(2)
+            .propagate_mode(PropagateMode::ChangeAll)
+            .notify_old_thread(false)
+            .send()
+            .await?;
+
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "topic=Castle&stream_id=2&propagate_mode=change_all\
+             &send_notification_to_old_thread=false"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn message_history() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "message_history": [
+                        {
+                            "topic": "Castle", "content": "Hi",
+                            "rendered_content": "<p>Hi</p>", "user_id": 10,
+                            "timestamp": 1530129122
+                        },
+                        {
+                            "topic": "Castle", "content": "Hello",
+                            "rendered_content": "<p>Hello</p>", "prev_content": "Hi",
+                            "prev_rendered_content": "<p>Hi</p>",
+                            "content_html_diff": "<div><p><span class=\"highlight_text_inserted\">Hello</span></p></div>",
+                            "user_id": 11, "timestamp": 1530129134
+                        },
+                        {
+                            "topic": "Moat", "prev_topic": "Castle", "stream": 2,
+                            "prev_stream": 1, "content": "Hello",
+                            "rendered_content": "<p>Hello</p>", "user_id": 11,
+                            "timestamp": 1530129140
+                        }
+                    ],
+                    "msg": "", "result": "success"
+                })),
+                "messages/42/history",
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let history = client.message_history(42).await?;
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[1].prev_content.as_deref(), Some("Hi"));
+        assert_eq!(history[1].user_id, Some(11));
+        assert_eq!(history[2].prev_topic.as_deref(), Some("Castle"));
+        assert_eq!(
+            (history[2].prev_stream, history[2].stream),
+            (Some(1), Some(2))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_message() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method("DELETE", success(), "messages/42").expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+        client.delete_message(42).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,333 @@
+use super::Message;
+use crate::endpoint::Endpoint;
+use crate::narrow::Narrow;
+use crate::{Client, Error};
+use futures_core::Stream;
+use reqwest::Method;
+use serde::{Deserialize, Serialize, Serializer};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Message to start fetching from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Id(u64),
+    Newest,
+    Oldest,
+    /// Oldest unread message, or the newest message if all are read.
+    FirstUnread,
+}
+
+impl From<u64> for Anchor {
+    fn from(id: u64) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl Serialize for Anchor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Id(id) => serializer.serialize_u64(*id),
+            Self::Newest => serializer.serialize_str("newest"),
+            Self::Oldest => serializer.serialize_str("oldest"),
+            Self::FirstUnread => serializer.serialize_str("first_unread"),
+        }
+    }
+}
+
+/// Direction in which to walk the history of messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Older,
+    Newer,
+}
+
+/// Builder of a message query, created with
+/// [`Client::get_messages`](crate::Client::get_messages).
+pub struct GetMessagesBuilder {
+    request: GetMessagesRequest,
+    client: Client,
+}
+
+impl GetMessagesBuilder {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            request: GetMessagesRequest {
+                anchor: Anchor::Newest,
+                include_anchor: None,
+                num_before: 0,
+                num_after: 0,
+                narrow: None,
+                client_gravatar: None,
+                apply_markdown: None,
+            },
+            client,
+        }
+    }
+
+    /// Defaults to [`Anchor::Newest`].
+    pub fn anchor<A: Into<Anchor>>(mut self, anchor: A) -> Self {
+        self.request.anchor = anchor.into();
+        self
+    }
+
+    pub fn include_anchor(mut self, value: bool) -> Self {
+        self.request.include_anchor.replace(value);
+        self
+    }
+
+    pub fn num_before(mut self, value: u32) -> Self {
+        self.request.num_before = value;
+        self
+    }
+
+    pub fn num_after(mut self, value: u32) -> Self {
+        self.request.num_after = value;
+        self
+    }
+
+    /// Only fetches the messages matching the narrow. Filters are added to
+    /// the ones of previous calls.
+    pub fn narrow<N: Into<Narrow>>(mut self, narrow: N) -> Self {
+        let filters = narrow.into().filters().to_vec();
+        self.request
+            .narrow
+            .get_or_insert_with(Narrow::new)
+            .extend(filters);
+        self
+    }
+
+    pub fn client_gravatar(mut self, value: bool) -> Self {
+        self.request.client_gravatar.replace(value);
+        self
+    }
+
+    /// Whether to fetch the content rendered as HTML, the default, or its
+    /// Markdown source.
+    pub fn apply_markdown(mut self, value: bool) -> Self {
+        self.request.apply_markdown.replace(value);
+        self
+    }
+
+    /// Fetches a single batch of messages around the anchor.
+    pub async fn fetch(self) -> Result<GetMessagesResponse, Error> {
+        fetch(&self.client, &self.request).await
+    }
+
+    /// Walks the history from the anchor in the given direction, fetching
+    /// `batch_size` messages at a time until the oldest or newest message is
+    /// reached. Messages of each page are sorted by ID.
+    ///
+    /// The number of messages before and after the anchor is ignored.
+    pub fn pages(self, direction: Direction, batch_size: u32) -> MessagePages {
+        let mut request = self.request;
+        let (num_before, num_after) = match direction {
+            Direction::Older => (batch_size, 0),
+            Direction::Newer => (0, batch_size),
+        };
+        request.num_before = num_before;
+        request.num_after = num_after;
+
+        MessagePages {
+            state: PageState::Idle(Cursor {
+                client: self.client,
+                request,
+                direction,
+                done: false,
+            }),
+        }
+    }
+}
+
+async fn fetch(
+    client: &Client,
+    request: &GetMessagesRequest,
+) -> Result<GetMessagesResponse, Error> {
+    if let Some(ref narrow) = request.narrow {
+        narrow.validate()?;
+    }
+    client.send(Method::GET, Endpoint::MESSAGES, request).await
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct GetMessagesRequest {
+    anchor: Anchor,
+    include_anchor: Option<bool>,
+    num_before: u32,
+    num_after: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::serialize")]
+    narrow: Option<Narrow>,
+    client_gravatar: Option<bool>,
+    apply_markdown: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GetMessagesResponse {
+    pub messages: Vec<Message>,
+    /// ID of the anchor, if a message ID or `first_unread` was requested.
+    pub anchor: Option<u64>,
+    #[serde(default)]
+    pub found_anchor: bool,
+    #[serde(default)]
+    pub found_oldest: bool,
+    #[serde(default)]
+    pub found_newest: bool,
+    /// Whether older messages are hidden by the message history limit of the
+    /// realm.
+    #[serde(default)]
+    pub history_limited: bool,
+}
+
+/// [`Stream`](futures_core::Stream) of the pages of messages walked by
+/// [`GetMessagesBuilder::pages`]. The stream ends after yielding an error.
+pub struct MessagePages {
+    state: PageState,
+}
+
+type PageFuture = Pin<Box<dyn Future<Output = (Cursor, Result<Vec<Message>, Error>)> + Send>>;
+
+enum PageState {
+    Idle(Cursor),
+    Fetching(PageFuture),
+    Done,
+}
+
+struct Cursor {
+    client: Client,
+    request: GetMessagesRequest,
+    direction: Direction,
+    done: bool,
+}
+
+impl Cursor {
+    async fn next_page(&mut self) -> Result<Vec<Message>, Error> {
+        let mut res = fetch(&self.client, &self.request).await?;
+        let anchor = match self.request.anchor {
+            Anchor::Id(id) if self.request.include_anchor == Some(false) => Some(id),
+            _ => None,
+        };
+        // Servers not supporting `include_anchor` return the anchor again
+        res.messages.retain(|m| Some(m.id) != anchor);
+        res.messages.sort_unstable_by_key(|m| m.id);
+
+        let next_anchor = match self.direction {
+            Direction::Older => res.messages.iter().map(|m| m.id).min(),
+            Direction::Newer => res.messages.iter().map(|m| m.id).max(),
+        };
+        let found_end = match self.direction {
+            Direction::Older => res.found_oldest,
+            Direction::Newer => res.found_newest,
+        };
+
+        match next_anchor {
+            Some(id) if !found_end => {
+                self.request.anchor = Anchor::Id(id);
+                self.request.include_anchor.replace(false);
+            }
+            _ => self.done = true,
+        }
+        Ok(res.messages)
+    }
+}
+
+impl Stream for MessagePages {
+    type Item = Result<Vec<Message>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match std::mem::replace(&mut self.state, PageState::Done) {
+                PageState::Idle(cursor) if cursor.done => return Poll::Ready(None),
+                PageState::Idle(mut cursor) => {
+                    self.state = PageState::Fetching(Box::pin(async move {
+                        let res = cursor.next_page().await;
+                        (cursor, res)
+                    }));
+                }
+                PageState::Fetching(mut fut) => match fut.as_mut().poll(cx) {
+                    Poll::Ready((cursor, Ok(messages))) => {
+                        self.state = PageState::Idle(cursor);
+                        return Poll::Ready(Some(Ok(messages)));
+                    }
+                    Poll::Ready((_, Err(e))) => return Poll::Ready(Some(Err(e))),
+                    Poll::Pending => {
+                        self.state = PageState::Fetching(fut);
+                        return Poll::Pending;
+                    }
+                },
+                PageState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::narrow::Filter;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    fn message(id: u64) -> Value {
+        json!({
+            "id": id, "sender_id": 10, "sender_email": "iago@zulip.com",
+            "sender_full_name": "Iago", "content": "Hi", "content_type": "text/html",
+            "type": "stream", "display_recipient": "Denmark", "stream_id": 1,
+            "recipient_id": 3, "subject": "Castle", "timestamp": 0
+        })
+    }
+
+    fn page(anchor: &str, ids: &[u64], found_oldest: bool) -> Mock {
+        let messages: Vec<_> = ids.iter().copied().map(message).collect();
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path(format!(
+                "{}{}",
+                Endpoint::BASE_API,
+                Endpoint::MESSAGES
+            )))
+            .and(matchers::query_param("anchor", anchor))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "messages": messages,
+                "found_anchor": false,
+                "found_oldest": found_oldest,
+                "found_newest": anchor == "newest",
+                "history_limited": false,
+                "result": "success"
+            })))
+            .expect(1)
+    }
+
+    #[tokio::test]
+    async fn message_pages() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server.register(page("newest", &[5, 6], false)).await;
+        server.register(page("5", &[4, 3], false)).await;
+        server.register(page("3", &[1], true)).await;
+
+        let client = Client::build(server.uri()).init().await?;
+        let pages: Vec<_> = client
+            .get_messages()
+            .narrow(Filter::stream("Denmark"))
+            .pages(Direction::Older, 2)
+            .collect()
+            .await;
+        let ids: Vec<Vec<u64>> = pages
+            .into_iter()
+            .map(|p| p.unwrap().iter().map(|m| m.id).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![5, 6], vec![3, 4], vec![1]]);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(
+            requests[0].url.query(),
+            Some("anchor=newest&num_before=2&num_after=0&narrow=%5B%7B%22operator%22%3A%22stream%22%2C%22operand%22%3A%22Denmark%22%2C%22negated%22%3Afalse%7D%5D")
+        );
+        assert_eq!(
+            requests[1].url.query(),
+            Some("anchor=5&include_anchor=false&num_before=2&num_after=0&narrow=%5B%7B%22operator%22%3A%22stream%22%2C%22operand%22%3A%22Denmark%22%2C%22negated%22%3Afalse%7D%5D")
+        );
+        Ok(())
+    }
+}
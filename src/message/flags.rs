@@ -0,0 +1,244 @@
+use super::Anchor;
+use crate::endpoint::Endpoint;
+use crate::narrow::Narrow;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Number of messages updated per request when updating a narrow.
+const NARROW_BATCH_SIZE: u32 = 1000;
+
+/// Personal flags of a message that can be changed by the user.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Flag {
+    Read,
+    Starred,
+    Collapsed,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FlagOp {
+    Add,
+    Remove,
+}
+
+/// Updates a flag on the given messages, returning the IDs of the messages
+/// that were updated.
+pub(crate) async fn update_flag(
+    client: &Client,
+    op: FlagOp,
+    flag: Flag,
+    message_ids: Vec<u64>,
+) -> Result<Vec<u64>, Error> {
+    let request = UpdateFlagRequest {
+        messages: message_ids,
+        op,
+        flag,
+    };
+    let res: UpdateFlagResponse = client
+        .send(Method::POST, Endpoint::MESSAGE_FLAGS, &request)
+        .await?;
+    Ok(res.messages)
+}
+
+/// Updates a flag on every message of a narrow, in batches from the oldest
+/// message, returning the number of messages that were updated.
+pub(crate) async fn update_flag_in_narrow(
+    client: &Client,
+    op: FlagOp,
+    flag: Flag,
+    narrow: Narrow,
+) -> Result<u64, Error> {
+    narrow.validate()?;
+    let mut request = UpdateFlagInNarrowRequest {
+        anchor: Anchor::Oldest,
+        include_anchor: true,
+        num_before: 0,
+        num_after: NARROW_BATCH_SIZE,
+        narrow,
+        op,
+        flag,
+    };
+
+    let mut updated = 0;
+    loop {
+        let res: UpdateFlagInNarrowResponse = client
+            .send(Method::POST, Endpoint::MESSAGE_FLAGS_NARROW, &request)
+            .await?;
+        updated += res.updated_count;
+
+        match res.last_processed_id {
+            Some(id) if !res.found_newest => {
+                request.anchor = Anchor::Id(id);
+                request.include_anchor = false;
+            }
+            _ => return Ok(updated),
+        }
+    }
+}
+
+pub(crate) async fn mark_all_as_read(client: &Client) -> Result<(), Error> {
+    loop {
+        let res: MarkAllAsReadResponse = client
+            .send(Method::POST, Endpoint::MARK_ALL_AS_READ, &EmptyRequest {})
+            .await?;
+        // The server may stop before the end when there are many messages
+        if res.complete != Some(false) {
+            return Ok(());
+        }
+    }
+}
+
+pub(crate) async fn mark_stream_as_read(client: &Client, stream_id: u64) -> Result<(), Error> {
+    let request = MarkAsReadRequest {
+        stream_id,
+        topic_name: None,
+    };
+    let _: EmptyResponse = client
+        .send(Method::POST, Endpoint::MARK_STREAM_AS_READ, &request)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn mark_topic_as_read(
+    client: &Client,
+    stream_id: u64,
+    topic: String,
+) -> Result<(), Error> {
+    let request = MarkAsReadRequest {
+        stream_id,
+        topic_name: Some(topic),
+    };
+    let _: EmptyResponse = client
+        .send(Method::POST, Endpoint::MARK_TOPIC_AS_READ, &request)
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+struct UpdateFlagRequest {
+    #[serde(serialize_with = "crate::serialize")]
+    messages: Vec<u64>,
+    op: FlagOp,
+    flag: Flag,
+}
+
+#[derive(Deserialize)]
+struct UpdateFlagResponse {
+    #[serde(default)]
+    messages: Vec<u64>,
+}
+
+#[derive(Serialize, Debug)]
+struct UpdateFlagInNarrowRequest {
+    anchor: Anchor,
+    include_anchor: bool,
+    num_before: u32,
+    num_after: u32,
+    #[serde(serialize_with = "crate::serialize")]
+    narrow: Narrow,
+    op: FlagOp,
+    flag: Flag,
+}
+
+#[derive(Deserialize)]
+struct UpdateFlagInNarrowResponse {
+    updated_count: u64,
+    last_processed_id: Option<u64>,
+    #[serde(default)]
+    found_newest: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct MarkAsReadRequest {
+    stream_id: u64,
+    topic_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MarkAllAsReadResponse {
+    complete: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::narrow::Filter;
+    use crate::test_util::{body_as_string, mock};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn update_flags() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "messages": [4], "msg": "", "result": "success" })),
+                Endpoint::MESSAGE_FLAGS,
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let updated = client.add_flag(Flag::Starred, [4, 8]).await?;
+        assert_eq!(updated, vec![4]);
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "messages=%5B4%2C8%5D&op=add&flag=starred"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_flags_in_narrow() -> Result<(), Box<dyn std::error::Error>> {
+        let batch = |last_processed_id: u64, found_newest: bool| {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "processed_count": 1000, "updated_count": 600,
+                "first_processed_id": 1, "last_processed_id": last_processed_id,
+                "found_oldest": true, "found_newest": found_newest,
+                "msg": "", "result": "success"
+            }))
+        };
+        let server = MockServer::start().await;
+        server
+            .register(mock(batch(1200, false), Endpoint::MESSAGE_FLAGS_NARROW).up_to_n_times(1))
+            .await;
+        server
+            .register(mock(batch(2400, true), Endpoint::MESSAGE_FLAGS_NARROW))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let updated = client
+            .add_flag_in_narrow(Flag::Read, Filter::stream_id(1))
+            .await?;
+        assert_eq!(updated, 1200);
+
+        // The next batch starts after the last processed message
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[1].body.clone())?;
+        assert!(body.starts_with("anchor=1200&include_anchor=false&num_before=0&num_after=1000"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mark_topic_as_read() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock(
+                ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" })),
+                Endpoint::MARK_TOPIC_AS_READ,
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client.mark_topic_as_read(1, "Castle").await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "stream_id=1&topic_name=Castle"
+        );
+        Ok(())
+    }
+}
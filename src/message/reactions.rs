@@ -0,0 +1,170 @@
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReactionType {
+    /// Unicode emoji, whose code is its codepoint in hexadecimal.
+    UnicodeEmoji,
+    /// Custom emoji of the realm, whose code is its ID.
+    RealmEmoji,
+    /// Special emoji of the server, e.g. `zulip`.
+    ZulipExtraEmoji,
+    /// Type added by a newer server, kept as sent to send it back.
+    Other(String),
+}
+
+impl ReactionType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::UnicodeEmoji => "unicode_emoji",
+            Self::RealmEmoji => "realm_emoji",
+            Self::ZulipExtraEmoji => "zulip_extra_emoji",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl From<String> for ReactionType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "unicode_emoji" => Self::UnicodeEmoji,
+            "realm_emoji" => Self::RealmEmoji,
+            "zulip_extra_emoji" => Self::ZulipExtraEmoji,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+impl Serialize for ReactionType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ReactionType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Emoji to react to a message with.
+///
+/// The server can look up the emoji by name, but its code is needed to react
+/// with an emoji whose name changed or was deactivated.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Emoji {
+    #[serde(rename = "emoji_name")]
+    name: String,
+    #[serde(rename = "emoji_code")]
+    code: Option<String>,
+    reaction_type: Option<ReactionType>,
+}
+
+impl Emoji {
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        Self {
+            name: name.into(),
+            code: None,
+            reaction_type: None,
+        }
+    }
+
+    pub fn with_code<C: Into<String>>(mut self, code: C, reaction_type: ReactionType) -> Self {
+        self.code.replace(code.into());
+        self.reaction_type.replace(reaction_type);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn reaction_type(&self) -> Option<&ReactionType> {
+        self.reaction_type.as_ref()
+    }
+}
+
+impl From<&str> for Emoji {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for Emoji {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+pub(crate) async fn update_reaction(
+    client: &Client,
+    method: Method,
+    message_id: u64,
+    emoji: &Emoji,
+) -> Result<(), Error> {
+    let endpoint = format!("{}/{}/reactions", Endpoint::MESSAGES, message_id);
+    let _: EmptyResponse = client.send(method, &endpoint, emoji).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Reaction;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn reactions() -> Result<(), Box<dyn std::error::Error>> {
+        let success =
+            ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }));
+        let server = MockServer::start().await;
+        server
+            .register(mock_method("POST", success.clone(), "messages/42/reactions").expect(1))
+            .await;
+        server
+            .register(mock_method("DELETE", success, "messages/42/reactions").expect(2))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client.add_reaction(42, "octopus").await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "emoji_name=octopus"
+        );
+
+        let reaction: Reaction = serde_json::from_value(json!({
+            "emoji_name": "green_tick", "emoji_code": "81",
+            "reaction_type": "realm_emoji", "user_id": 10
+        }))?;
+        client.remove_reaction(42, reaction.emoji()).await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "emoji_name=green_tick&emoji_code=81&reaction_type=realm_emoji"
+        );
+
+        // Types unknown to this library are sent back as received
+        let reaction: Reaction = serde_json::from_value(json!({
+            "emoji_name": "party", "emoji_code": "p1",
+            "reaction_type": "animated_emoji", "user_id": 10
+        }))?;
+        assert_eq!(
+            reaction.reaction_type,
+            ReactionType::Other("animated_emoji".into())
+        );
+        client.remove_reaction(42, reaction.emoji()).await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "emoji_name=party&emoji_code=p1&reaction_type=animated_emoji"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use super::{Destination, MessageType};
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Message scheduled to be sent by the server at a later time.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduledMessage {
+    pub scheduled_message_id: u64,
+    #[serde(rename = "type")]
+    pub kind: MessageType,
+    pub to: ScheduledRecipient,
+    /// Topic of a stream message.
+    pub topic: Option<String>,
+    pub content: String,
+    pub rendered_content: String,
+    /// UNIX timestamp, in seconds, at which the message will be sent.
+    pub scheduled_delivery_timestamp: i64,
+    /// Whether the server failed to send the message at the scheduled time.
+    #[serde(default)]
+    pub failed: bool,
+}
+
+impl ScheduledMessage {
+    pub fn destination(&self) -> Destination {
+        match &self.to {
+            ScheduledRecipient::Stream(id) => {
+                Destination::stream_id(*id, self.topic.clone().unwrap_or_default())
+            }
+            ScheduledRecipient::Users(ids) => Destination::user_ids(ids.iter().copied()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ScheduledRecipient {
+    Stream(u64),
+    Users(Vec<u64>),
+}
+
+/// Builder of a scheduled message, created with
+/// [`Client::schedule_message`](crate::Client::schedule_message).
+pub struct ScheduleMessageBuilder {
+    destination: Destination,
+    request: ScheduledMessageRequest,
+    client: Client,
+}
+
+impl ScheduleMessageBuilder {
+    pub(crate) fn new(client: Client, to: Destination, content: String, timestamp: i64) -> Self {
+        Self {
+            destination: to,
+            request: ScheduledMessageRequest {
+                content: Some(content),
+                scheduled_delivery_timestamp: Some(timestamp),
+                ..Default::default()
+            },
+            client,
+        }
+    }
+
+    /// Whether the message is marked as read for the sender once sent. The
+    /// server decides by default, based on the client.
+    pub fn read_by_sender(mut self, value: bool) -> Self {
+        self.request.read_by_sender.replace(value);
+        self
+    }
+
+    /// Schedules the message, returning the ID of the scheduled message.
+    pub async fn send(mut self) -> Result<u64, Error> {
+        self.request.set_destination(&self.destination)?;
+
+        let res: CreateScheduledMessageResponse = self
+            .client
+            .send(Method::POST, Endpoint::SCHEDULED_MESSAGES, &self.request)
+            .await?;
+        Ok(res.scheduled_message_id)
+    }
+}
+
+/// Builder of an edit of a scheduled message, created with
+/// [`Client::edit_scheduled_message`](crate::Client::edit_scheduled_message).
+pub struct EditScheduledMessageBuilder {
+    scheduled_message_id: u64,
+    destination: Option<Destination>,
+    request: ScheduledMessageRequest,
+    client: Client,
+}
+
+impl EditScheduledMessageBuilder {
+    pub(crate) fn new(client: Client, scheduled_message_id: u64) -> Self {
+        Self {
+            scheduled_message_id,
+            destination: None,
+            request: ScheduledMessageRequest::default(),
+            client,
+        }
+    }
+
+    /// Scheduled messages can only be sent to streams and users by ID, other
+    /// destinations are rejected with [`UnsupportedDestination`] on send.
+    pub fn to(mut self, destination: Destination) -> Self {
+        self.destination.replace(destination);
+        self
+    }
+
+    pub fn content<C: Into<String>>(mut self, content: C) -> Self {
+        self.request.content.replace(content.into());
+        self
+    }
+
+    /// UNIX timestamp, in seconds, at which to send the message.
+    pub fn deliver_at(mut self, timestamp: i64) -> Self {
+        self.request.scheduled_delivery_timestamp.replace(timestamp);
+        self
+    }
+
+    pub async fn send(mut self) -> Result<(), Error> {
+        if let Some(ref destination) = self.destination {
+            self.request.set_destination(destination)?;
+        }
+
+        let endpoint = endpoint(self.scheduled_message_id);
+        let _: EmptyResponse = self
+            .client
+            .send(Method::PATCH, &endpoint, &self.request)
+            .await?;
+        Ok(())
+    }
+}
+
+pub(crate) async fn list(client: &Client) -> Result<Vec<ScheduledMessage>, Error> {
+    let res: ScheduledMessagesResponse = client
+        .send(Method::GET, Endpoint::SCHEDULED_MESSAGES, &EmptyRequest {})
+        .await?;
+    Ok(res.scheduled_messages)
+}
+
+pub(crate) async fn delete(client: &Client, scheduled_message_id: u64) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(
+            Method::DELETE,
+            &endpoint(scheduled_message_id),
+            &EmptyRequest {},
+        )
+        .await?;
+    Ok(())
+}
+
+fn endpoint(scheduled_message_id: u64) -> String {
+    format!("{}/{}", Endpoint::SCHEDULED_MESSAGES, scheduled_message_id)
+}
+
+#[derive(Serialize, Debug, Default)]
+struct ScheduledMessageRequest {
+    #[serde(rename = "type")]
+    kind: Option<MessageType>,
+    to: Option<String>,
+    topic: Option<String>,
+    content: Option<String>,
+    scheduled_delivery_timestamp: Option<i64>,
+    read_by_sender: Option<bool>,
+}
+
+impl ScheduledMessageRequest {
+    fn set_destination(&mut self, destination: &Destination) -> Result<(), UnsupportedDestination> {
+        if let Destination::Stream { .. } | Destination::UserEmails(_) = destination {
+            return Err(UnsupportedDestination(destination.clone()));
+        }
+
+        self.kind.replace(destination.kind());
+        self.to.replace(destination.to());
+        self.topic = destination.topic().map(str::to_owned);
+        Ok(())
+    }
+}
+
+/// Destination not supported by scheduled messages, i.e. a stream by name
+/// or users by email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedDestination(pub Destination);
+
+impl fmt::Display for UnsupportedDestination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("scheduled messages can only be sent to streams and users by ID")
+    }
+}
+
+impl std::error::Error for UnsupportedDestination {}
+
+#[derive(Deserialize)]
+struct ScheduledMessagesResponse {
+    scheduled_messages: Vec<ScheduledMessage>,
+}
+
+#[derive(Deserialize)]
+struct CreateScheduledMessageResponse {
+    scheduled_message_id: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn scheduled_messages() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "scheduled_messages": [{
+                        "scheduled_message_id": 27, "type": "stream", "to": 1,
+                        "topic": "Castle", "content": "Hi", "rendered_content": "<p>Hi</p>",
+                        "scheduled_delivery_timestamp": 1681662420, "failed": false
+                    }],
+                    "msg": "", "result": "success"
+                })),
+                Endpoint::SCHEDULED_MESSAGES,
+            ))
+            .await;
+        server
+            .register(mock_method(
+                "POST",
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "scheduled_message_id": 28, "msg": "", "result": "success"
+                })),
+                Endpoint::SCHEDULED_MESSAGES,
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let scheduled = client.scheduled_messages().await?;
+        assert_eq!(scheduled[0].scheduled_message_id, 27);
+        assert_eq!(
+            scheduled[0].destination(),
+            Destination::stream_id(1, "Castle")
+        );
+
+        let id = client
+            .schedule_message(Destination::user_ids([10, 11]), "Hi", 1681662420)
+            .read_by_sender(true)
+            .send()
+            .await?;
+        assert_eq!(id, 28);
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "type=private&to=%5B10%2C11%5D&content=Hi&scheduled_delivery_timestamp=1681662420\
+             &read_by_sender=true"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_scheduled_message() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(
+                mock_method(
+                    "PATCH",
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({ "msg": "", "result": "success" })),
+                    "scheduled_messages/27",
+                )
+                .expect(1),
+            )
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .edit_scheduled_message(27)
+            .deliver_at(1681662480)
+            .send()
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "scheduled_delivery_timestamp=1681662480"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsupported_destination() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let error = client
+            .schedule_message(Destination::stream("general", "Castle"), "Hi", 1681662420)
+            .send()
+            .await
+            .expect_err("Streams by name should be rejected");
+        assert!(error.is_build());
+
+        let error = client
+            .edit_scheduled_message(27)
+            .to(Destination::user_emails(["iago@zulip.com"]))
+            .send()
+            .await
+            .expect_err("Users by email should be rejected");
+        assert!(error.is_build());
+        assert!(server.received_requests().await.unwrap().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,472 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Set of filters selecting messages, as used to search messages or to
+/// restrict the events of a queue. A message must match every filter.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Narrow {
+    filters: Vec<Filter>,
+}
+
+impl Narrow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a filter, unless it is already part of the narrow.
+    pub fn and(mut self, filter: Filter) -> Self {
+        self.push(filter);
+        self
+    }
+
+    pub fn filters(&self) -> &[Filter] {
+        self.filters.as_slice()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Checks the filters can be combined, so that mistakes are caught before
+    /// sending a request.
+    pub fn validate(&self) -> Result<(), NarrowError> {
+        for (i, filter) in self.filters.iter().enumerate() {
+            if filter.operand.is_empty() {
+                return Err(NarrowError::EmptyOperand(filter.operator));
+            }
+            if filter.negated && !filter.operator.can_negate() {
+                return Err(NarrowError::Negated(filter.operator));
+            }
+            if filter.negated || !filter.operator.is_unique() {
+                continue;
+            }
+
+            for other in self.filters[i + 1..].iter().filter(|f| !f.negated) {
+                if other.operator == filter.operator {
+                    return Err(NarrowError::Duplicated(filter.operator));
+                }
+                if filter.operator.conflicts_with(other.operator) {
+                    return Err(NarrowError::Conflicting(filter.operator, other.operator));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Narrow::validate`], but also checks the filters are supported
+    /// by the narrow of an event queue, which only selects messages by
+    /// stream, topic, sender and flags.
+    pub fn validate_for_events(&self) -> Result<(), NarrowError> {
+        self.validate()?;
+        for filter in &self.filters {
+            if !filter.operator.for_events() {
+                return Err(NarrowError::Unsupported(filter.operator));
+            }
+            // The followed topics of the user are not known to event queues
+            if filter.operator == Operator::Is && filter.operand == Is::Followed.into() {
+                return Err(NarrowError::Unsupported(filter.operator));
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, filter: Filter) {
+        if !self.filters.contains(&filter) {
+            self.filters.push(filter);
+        }
+    }
+}
+
+impl From<Filter> for Narrow {
+    fn from(filter: Filter) -> Self {
+        Self {
+            filters: vec![filter],
+        }
+    }
+}
+
+impl FromIterator<Filter> for Narrow {
+    fn from_iter<I: IntoIterator<Item = Filter>>(iter: I) -> Self {
+        let mut narrow = Self::new();
+        narrow.extend(iter);
+        narrow
+    }
+}
+
+impl Extend<Filter> for Narrow {
+    fn extend<I: IntoIterator<Item = Filter>>(&mut self, iter: I) {
+        for filter in iter {
+            self.push(filter);
+        }
+    }
+}
+
+/// Single condition of a [`Narrow`], serialized as an
+/// `{operator, operand, negated}` object.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    operator: Operator,
+    operand: Operand,
+    #[serde(default)]
+    negated: bool,
+}
+
+impl Filter {
+    fn new<T: Into<Operand>>(operator: Operator, operand: T) -> Self {
+        Self {
+            operator,
+            operand: operand.into(),
+            negated: false,
+        }
+    }
+
+    pub fn stream<T: Into<String>>(name: T) -> Self {
+        Self::new(Operator::Stream, name.into())
+    }
+
+    pub fn stream_id(stream_id: u64) -> Self {
+        Self::new(Operator::Stream, stream_id)
+    }
+
+    pub fn topic<T: Into<String>>(topic: T) -> Self {
+        Self::new(Operator::Topic, topic.into())
+    }
+
+    pub fn sender(user_id: u64) -> Self {
+        Self::new(Operator::Sender, user_id)
+    }
+
+    pub fn sender_email<T: Into<String>>(email: T) -> Self {
+        Self::new(Operator::Sender, email.into())
+    }
+
+    /// Direct messages with exactly the given users.
+    pub fn dm<I: IntoIterator<Item = u64>>(user_ids: I) -> Self {
+        Self::new(Operator::Dm, Operand::Ids(user_ids.into_iter().collect()))
+    }
+
+    /// Like [`Filter::dm`], but with users by email.
+    pub fn dm_emails<I, T>(emails: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let emails: Vec<String> = emails.into_iter().map(Into::into).collect();
+        Self::new(Operator::Dm, emails.join(","))
+    }
+
+    pub fn is(value: Is) -> Self {
+        Self::new(Operator::Is, value)
+    }
+
+    pub fn has(value: Has) -> Self {
+        Self::new(Operator::Has, value.as_str().to_owned())
+    }
+
+    /// Full-text search of the keywords in the content and topic.
+    pub fn search<T: Into<String>>(keywords: T) -> Self {
+        Self::new(Operator::Search, keywords.into())
+    }
+
+    /// Selects every message of the narrow, anchored at the given message.
+    pub fn near(message_id: u64) -> Self {
+        Self::new(Operator::Near, message_id)
+    }
+
+    pub fn id(message_id: u64) -> Self {
+        Self::new(Operator::Id, message_id)
+    }
+
+    /// Conversation of the given message, even if it was moved.
+    pub fn with(message_id: u64) -> Self {
+        Self::new(Operator::With, message_id)
+    }
+
+    /// Selects the messages not matching the filter instead.
+    pub fn negate(mut self) -> Self {
+        self.negated = !self.negated;
+        self
+    }
+
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    pub fn operand(&self) -> &Operand {
+        &self.operand
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Operator {
+    Stream,
+    Topic,
+    Sender,
+    Dm,
+    Is,
+    Has,
+    Search,
+    Near,
+    Id,
+    With,
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stream => "stream",
+            Self::Topic => "topic",
+            Self::Sender => "sender",
+            Self::Dm => "dm",
+            Self::Is => "is",
+            Self::Has => "has",
+            Self::Search => "search",
+            Self::Near => "near",
+            Self::Id => "id",
+            Self::With => "with",
+        }
+    }
+
+    /// Whether a message can match only one operand of the operator.
+    fn is_unique(&self) -> bool {
+        !matches!(self, Self::Is | Self::Has | Self::Search)
+    }
+
+    /// Anchors rather than filters cannot be negated.
+    fn can_negate(&self) -> bool {
+        !matches!(self, Self::Near | Self::With)
+    }
+
+    /// Whether the operator can be used in the narrow of an event queue.
+    fn for_events(&self) -> bool {
+        matches!(self, Self::Stream | Self::Topic | Self::Sender | Self::Is)
+    }
+
+    fn conflicts_with(&self, other: Operator) -> bool {
+        matches!(
+            (self, other),
+            (Self::Stream | Self::Topic, Self::Dm) | (Self::Dm, Self::Stream | Self::Topic)
+        )
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Operand {
+    Id(u64),
+    Ids(Vec<u64>),
+    Text(String),
+}
+
+impl Operand {
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Id(_) => false,
+            Self::Ids(ids) => ids.is_empty(),
+            Self::Text(text) => text.trim().is_empty(),
+        }
+    }
+}
+
+impl From<u64> for Operand {
+    fn from(id: u64) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<String> for Operand {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<Is> for Operand {
+    fn from(value: Is) -> Self {
+        Self::Text(value.as_str().to_owned())
+    }
+}
+
+/// Operands of the `is` operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Is {
+    Alerted,
+    Mentioned,
+    Starred,
+    Unread,
+    Dm,
+    Resolved,
+    Followed,
+}
+
+impl Is {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alerted => "alerted",
+            Self::Mentioned => "mentioned",
+            Self::Starred => "starred",
+            Self::Unread => "unread",
+            Self::Dm => "dm",
+            Self::Resolved => "resolved",
+            Self::Followed => "followed",
+        }
+    }
+}
+
+/// Operands of the `has` operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Has {
+    Link,
+    Attachment,
+    Image,
+    Reaction,
+}
+
+impl Has {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Link => "link",
+            Self::Attachment => "attachment",
+            Self::Image => "image",
+            Self::Reaction => "reaction",
+        }
+    }
+}
+
+/// Invalid combination of filters in a [`Narrow`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NarrowError {
+    EmptyOperand(Operator),
+    Negated(Operator),
+    Duplicated(Operator),
+    Conflicting(Operator, Operator),
+    /// Filter not supported by the narrow of an event queue.
+    Unsupported(Operator),
+}
+
+impl fmt::Display for NarrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyOperand(op) => write!(f, "empty operand for '{}'", op),
+            Self::Negated(op) => write!(f, "'{}' cannot be negated", op),
+            Self::Duplicated(op) => write!(f, "'{}' used more than once", op),
+            Self::Conflicting(a, b) => write!(f, "'{}' cannot be combined with '{}'", a, b),
+            Self::Unsupported(op) => write!(f, "'{}' not supported by event queues", op),
+        }
+    }
+}
+
+impl std::error::Error for NarrowError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn narrow() {
+        let narrow: Narrow = [
+            Filter::stream_id(1),
+            Filter::topic("Castle"),
+            Filter::is(Is::Unread).negate(),
+            Filter::is(Is::Unread).negate(),
+            Filter::sender_email("iago@zulip.com"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(narrow.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&narrow).unwrap(),
+            json!([
+                { "operator": "stream", "operand": 1, "negated": false },
+                { "operator": "topic", "operand": "Castle", "negated": false },
+                { "operator": "is", "operand": "unread", "negated": true },
+                { "operator": "sender", "operand": "iago@zulip.com", "negated": false },
+            ])
+        );
+        let json = serde_json::to_string(&narrow).unwrap();
+        assert_eq!(serde_json::from_str::<Narrow>(&json).unwrap(), narrow);
+    }
+
+    #[test]
+    fn validate() {
+        let validate = |filters: Vec<Filter>| filters.into_iter().collect::<Narrow>().validate();
+
+        assert_eq!(
+            validate(vec![Filter::topic(" ")]),
+            Err(NarrowError::EmptyOperand(Operator::Topic))
+        );
+        assert_eq!(
+            validate(vec![Filter::near(1).negate()]),
+            Err(NarrowError::Negated(Operator::Near))
+        );
+        assert_eq!(
+            validate(vec![Filter::stream("Denmark"), Filter::stream("Sweden")]),
+            Err(NarrowError::Duplicated(Operator::Stream))
+        );
+        assert_eq!(
+            validate(vec![Filter::dm([10]), Filter::topic("Castle")]),
+            Err(NarrowError::Conflicting(Operator::Dm, Operator::Topic))
+        );
+        // Negated filters can be combined freely
+        assert!(validate(vec![
+            Filter::stream("Denmark"),
+            Filter::stream("Sweden").negate(),
+            Filter::dm([10]).negate(),
+            Filter::has(Has::Link),
+            Filter::has(Has::Image),
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn validate_for_events() {
+        let validate = |filters: Vec<Filter>| {
+            filters
+                .into_iter()
+                .collect::<Narrow>()
+                .validate_for_events()
+        };
+
+        assert!(validate(vec![
+            Filter::stream("Denmark"),
+            Filter::topic("Castle"),
+            Filter::sender(10).negate(),
+            Filter::is(Is::Mentioned),
+        ])
+        .is_ok());
+        assert_eq!(
+            validate(vec![Filter::stream("Denmark"), Filter::near(1)]),
+            Err(NarrowError::Unsupported(Operator::Near))
+        );
+        assert_eq!(
+            validate(vec![Filter::search("castle")]),
+            Err(NarrowError::Unsupported(Operator::Search))
+        );
+        assert_eq!(
+            validate(vec![Filter::is(Is::Followed)]),
+            Err(NarrowError::Unsupported(Operator::Is))
+        );
+        // Errors of every narrow are reported first
+        assert_eq!(
+            validate(vec![Filter::with(1).negate()]),
+            Err(NarrowError::Negated(Operator::With))
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RealmEmoji {
+    pub id: String,
+    pub name: String,
+    pub source_url: String,
+    #[serde(default)]
+    pub deactivated: bool,
+    pub author_id: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CustomProfileField {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: i32,
+    pub order: i32,
+    pub name: String,
+    #[serde(default)]
+    pub hint: String,
+    #[serde(default)]
+    pub field_data: String,
+    #[serde(default)]
+    pub display_in_profile_summary: bool,
+}
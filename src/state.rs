@@ -0,0 +1,564 @@
+use crate::event::{
+    Event, EventKind, InitialState, Queue, RealmEvent, RealmUserEvent, StreamEvent,
+    SubscriptionEvent, UserGroupEvent, UserUpdate,
+};
+use crate::realm::RealmEmoji;
+use crate::stream::{Stream, Subscription, SubscriptionProperty};
+use crate::user::{ProfileData, User, UserGroup, UserRole};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Local mirror of the state of a realm.
+///
+/// The state is seeded from the initial state of a [`Queue`] and kept current
+/// by [applying](RealmState::apply) every event received from it. The queue
+/// must be registered for the events of the data to mirror, e.g. `realm_user`,
+/// `stream`, `subscription`, `user_group`, `realm_emoji` and `realm`.
+#[derive(Debug, Default)]
+pub struct RealmState {
+    snapshot: Arc<RealmSnapshot>,
+}
+
+impl RealmState {
+    pub fn new(queue: &Queue) -> Self {
+        Self::from_initial_state(queue.initial_state())
+    }
+
+    pub fn from_initial_state(state: &InitialState) -> Self {
+        Self {
+            snapshot: Arc::new(RealmSnapshot::from(state)),
+        }
+    }
+
+    /// Returns a read-only view of the current state.
+    ///
+    /// Snapshots are cheap to take; the state is only copied when an event is
+    /// applied while a snapshot is still alive.
+    pub fn snapshot(&self) -> Arc<RealmSnapshot> {
+        self.snapshot.clone()
+    }
+
+    /// Updates the state with an event. Events that do not affect the state
+    /// are ignored.
+    ///
+    /// When the queue was registered again, the state is seeded from scratch
+    /// with the new initial state.
+    pub fn apply(&mut self, event: &Event) {
+        match event.kind() {
+            EventKind::Resynchronized(registration) => {
+                self.snapshot = Arc::new(RealmSnapshot::from(registration.initial_state()));
+            }
+            EventKind::Message(e) => {
+                let snapshot = Arc::make_mut(&mut self.snapshot);
+                let max = snapshot.max_message_id.get_or_insert(e.message.id);
+                *max = (*max).max(e.message.id);
+            }
+            EventKind::RealmUser(e) => Arc::make_mut(&mut self.snapshot).apply_realm_user(e),
+            EventKind::Stream(e) => Arc::make_mut(&mut self.snapshot).apply_stream(e),
+            EventKind::Subscription(e) => Arc::make_mut(&mut self.snapshot).apply_subscription(e),
+            EventKind::UserGroup(e) => Arc::make_mut(&mut self.snapshot).apply_user_group(e),
+            EventKind::RealmEmoji(e) => {
+                Arc::make_mut(&mut self.snapshot).emoji = e.realm_emoji.clone();
+            }
+            EventKind::Realm(e) => Arc::make_mut(&mut self.snapshot).apply_realm(e),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RealmSnapshot {
+    max_message_id: Option<u64>,
+    users: HashMap<u64, User>,
+    streams: HashMap<u64, Stream>,
+    subscriptions: HashMap<u64, Subscription>,
+    user_groups: HashMap<u64, UserGroup>,
+    emoji: HashMap<String, RealmEmoji>,
+    settings: HashMap<String, Value>,
+}
+
+impl RealmSnapshot {
+    pub fn max_message_id(&self) -> Option<u64> {
+        self.max_message_id
+    }
+
+    /// Users of the realm, including deactivated users and bots, by ID.
+    pub fn users(&self) -> &HashMap<u64, User> {
+        &self.users
+    }
+
+    pub fn user(&self, user_id: u64) -> Option<&User> {
+        self.users.get(&user_id)
+    }
+
+    pub fn user_by_email(&self, email: &str) -> Option<&User> {
+        self.users.values().find(|u| u.email == email)
+    }
+
+    /// Streams visible to the user, by ID.
+    pub fn streams(&self) -> &HashMap<u64, Stream> {
+        &self.streams
+    }
+
+    pub fn stream(&self, stream_id: u64) -> Option<&Stream> {
+        self.streams.get(&stream_id)
+    }
+
+    pub fn stream_by_name(&self, name: &str) -> Option<&Stream> {
+        self.streams.values().find(|s| s.name == name)
+    }
+
+    /// Streams the user is subscribed to, by stream ID.
+    pub fn subscriptions(&self) -> &HashMap<u64, Subscription> {
+        &self.subscriptions
+    }
+
+    pub fn subscription(&self, stream_id: u64) -> Option<&Subscription> {
+        self.subscriptions.get(&stream_id)
+    }
+
+    pub fn user_groups(&self) -> &HashMap<u64, UserGroup> {
+        &self.user_groups
+    }
+
+    pub fn user_group(&self, group_id: u64) -> Option<&UserGroup> {
+        self.user_groups.get(&group_id)
+    }
+
+    /// Custom emoji of the realm, by ID.
+    pub fn emoji(&self) -> &HashMap<String, RealmEmoji> {
+        &self.emoji
+    }
+
+    /// Realm settings, keyed by their name in the register response, e.g.
+    /// `realm_name`.
+    pub fn settings(&self) -> &HashMap<String, Value> {
+        &self.settings
+    }
+
+    pub fn setting(&self, name: &str) -> Option<&Value> {
+        self.settings.get(name)
+    }
+
+    fn apply_realm_user(&mut self, event: &RealmUserEvent) {
+        match event {
+            RealmUserEvent::Add { person } => {
+                self.users.insert(person.user_id, person.clone());
+            }
+            // Older servers remove deactivated users, which are kept so that
+            // their messages can still be attributed
+            RealmUserEvent::Remove { person } => {
+                if let Some(user) = self.users.get_mut(&person.user_id) {
+                    user.is_active = false;
+                }
+            }
+            RealmUserEvent::Update { person } => {
+                if let Some(user) = self.users.get_mut(&person.user_id) {
+                    update_user(user, person);
+                }
+            }
+        }
+    }
+
+    fn apply_stream(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::Create { streams } => {
+                for stream in streams {
+                    self.streams.insert(stream.stream_id, stream.clone());
+                }
+            }
+            StreamEvent::Delete { streams } => {
+                for stream in streams {
+                    self.streams.remove(&stream.stream_id);
+                    self.subscriptions.remove(&stream.stream_id);
+                }
+            }
+            StreamEvent::Update {
+                stream_id,
+                property,
+                value,
+                rendered_description,
+                ..
+            } => {
+                if let Some(stream) = self.streams.get_mut(stream_id) {
+                    update_stream(stream, property, value, rendered_description);
+                }
+                if let Some(sub) = self.subscriptions.get_mut(stream_id) {
+                    update_subscribed_stream(sub, property, value, rendered_description);
+                }
+            }
+        }
+    }
+
+    fn apply_subscription(&mut self, event: &SubscriptionEvent) {
+        match event {
+            SubscriptionEvent::Add { subscriptions } => {
+                for sub in subscriptions {
+                    self.subscriptions.insert(sub.stream_id, sub.clone());
+                }
+            }
+            SubscriptionEvent::Remove { subscriptions } => {
+                for sub in subscriptions {
+                    self.subscriptions.remove(&sub.stream_id);
+                }
+            }
+            SubscriptionEvent::Update {
+                stream_id,
+                property,
+            } => {
+                if let Some(sub) = self.subscriptions.get_mut(stream_id) {
+                    update_subscription(sub, property);
+                }
+            }
+            SubscriptionEvent::PeerAdd {
+                stream_ids,
+                user_ids,
+            } => {
+                for subscribers in self.subscribers_of(stream_ids) {
+                    extend_unique(subscribers, user_ids);
+                }
+            }
+            SubscriptionEvent::PeerRemove {
+                stream_ids,
+                user_ids,
+            } => {
+                for subscribers in self.subscribers_of(stream_ids) {
+                    subscribers.retain(|id| !user_ids.contains(id));
+                }
+            }
+        }
+    }
+
+    fn subscribers_of<'a>(
+        &'a mut self,
+        stream_ids: &'a [u64],
+    ) -> impl Iterator<Item = &'a mut Vec<u64>> {
+        self.subscriptions
+            .values_mut()
+            .filter(|s| stream_ids.contains(&s.stream_id))
+            .filter_map(|s| s.subscribers.as_mut())
+    }
+
+    fn apply_user_group(&mut self, event: &UserGroupEvent) {
+        match event {
+            UserGroupEvent::Add { group } => {
+                self.user_groups.insert(group.id, group.clone());
+            }
+            UserGroupEvent::Remove { group_id } => {
+                self.user_groups.remove(group_id);
+            }
+            UserGroupEvent::Update { group_id, data } => {
+                if let Some(group) = self.user_groups.get_mut(group_id) {
+                    if let Some(name) = data.get("name").and_then(Value::as_str) {
+                        group.name = name.to_owned();
+                    }
+                    if let Some(description) = data.get("description").and_then(Value::as_str) {
+                        group.description = description.to_owned();
+                    }
+                }
+            }
+            UserGroupEvent::AddMembers { group_id, user_ids } => {
+                if let Some(group) = self.user_groups.get_mut(group_id) {
+                    extend_unique(&mut group.members, user_ids);
+                }
+            }
+            UserGroupEvent::RemoveMembers { group_id, user_ids } => {
+                if let Some(group) = self.user_groups.get_mut(group_id) {
+                    group.members.retain(|id| !user_ids.contains(id));
+                }
+            }
+            UserGroupEvent::AddSubgroups {
+                group_id,
+                direct_subgroup_ids,
+            } => {
+                if let Some(group) = self.user_groups.get_mut(group_id) {
+                    extend_unique(&mut group.direct_subgroup_ids, direct_subgroup_ids);
+                }
+            }
+            UserGroupEvent::RemoveSubgroups {
+                group_id,
+                direct_subgroup_ids,
+            } => {
+                if let Some(group) = self.user_groups.get_mut(group_id) {
+                    group
+                        .direct_subgroup_ids
+                        .retain(|id| !direct_subgroup_ids.contains(id));
+                }
+            }
+        }
+    }
+
+    fn apply_realm(&mut self, event: &RealmEvent) {
+        match event {
+            RealmEvent::Update { property, value } => {
+                self.settings
+                    .insert(format!("realm_{}", property), value.clone());
+            }
+            RealmEvent::UpdateDict { data, .. } => {
+                for (key, value) in data {
+                    self.settings
+                        .insert(format!("realm_{}", key), value.clone());
+                }
+            }
+            RealmEvent::Deactivated { .. } => {
+                self.settings
+                    .insert("realm_deactivated".to_owned(), Value::Bool(true));
+            }
+        }
+    }
+}
+
+impl From<&InitialState> for RealmSnapshot {
+    fn from(state: &InitialState) -> Self {
+        let users = [
+            &state.realm_users,
+            &state.realm_non_active_users,
+            &state.cross_realm_bots,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|u| (u.user_id, u.clone()))
+        .collect();
+
+        Self {
+            max_message_id: state.max_message_id,
+            users,
+            streams: collect_by_id(&state.streams, |s| s.stream_id),
+            subscriptions: collect_by_id(&state.subscriptions, |s| s.stream_id),
+            user_groups: collect_by_id(&state.realm_user_groups, |g| g.id),
+            emoji: state.realm_emoji.clone().unwrap_or_default(),
+            settings: state
+                .other
+                .iter()
+                .filter(|(k, _)| k.starts_with("realm_"))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+}
+
+fn collect_by_id<T, F>(items: &Option<Vec<T>>, id: F) -> HashMap<u64, T>
+where
+    T: Clone,
+    F: Fn(&T) -> u64,
+{
+    items
+        .iter()
+        .flatten()
+        .map(|item| (id(item), item.clone()))
+        .collect()
+}
+
+fn extend_unique(ids: &mut Vec<u64>, new_ids: &[u64]) {
+    for id in new_ids {
+        if !ids.contains(id) {
+            ids.push(*id);
+        }
+    }
+}
+
+fn update_user(user: &mut User, update: &UserUpdate) {
+    for (property, value) in &update.data {
+        match property.as_str() {
+            "full_name" => set_string(&mut user.full_name, value),
+            "new_email" => set_string(&mut user.email, value),
+            "timezone" => set_string(&mut user.timezone, value),
+            "delivery_email" => user.delivery_email = value.as_str().map(str::to_owned),
+            "avatar_url" => user.avatar_url = value.as_str().map(str::to_owned),
+            "avatar_version" => user.avatar_version = value.as_i64().map(|v| v as i32),
+            "bot_owner_id" => user.bot_owner_id = value.as_u64(),
+            "is_active" => set_bool(&mut user.is_active, value),
+            "is_billing_admin" => set_bool(&mut user.is_billing_admin, value),
+            "role" => {
+                let role = value.as_i64().map(|v| UserRole::try_from(v as i32));
+                if let Some(Ok(role)) = role {
+                    set_role(user, role);
+                }
+            }
+            "custom_profile_field" => {
+                let id = value.get("id").and_then(Value::as_u64);
+                let data = value.get("value").and_then(Value::as_str);
+                match (id, data) {
+                    (Some(id), Some(data)) => {
+                        let field = ProfileData {
+                            value: data.to_owned(),
+                            rendered_value: value
+                                .get("rendered_value")
+                                .and_then(Value::as_str)
+                                .map(str::to_owned),
+                        };
+                        user.profile_data.insert(id.to_string(), field);
+                    }
+                    (Some(id), None) => {
+                        user.profile_data.remove(&id.to_string());
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_role(user: &mut User, role: UserRole) {
+    user.role = Some(role);
+    user.is_owner = role == UserRole::Owner;
+    user.is_admin = role.is_admin();
+    user.is_guest = role == UserRole::Guest;
+}
+
+fn update_stream(stream: &mut Stream, property: &str, value: &Value, rendered: &Option<String>) {
+    match property {
+        "name" => set_string(&mut stream.name, value),
+        "description" => {
+            set_string(&mut stream.description, value);
+            if let Some(rendered) = rendered {
+                stream.rendered_description = rendered.clone();
+            }
+        }
+        "invite_only" => set_bool(&mut stream.invite_only, value),
+        "is_web_public" => set_bool(&mut stream.is_web_public, value),
+        "history_public_to_subscribers" => {
+            set_bool(&mut stream.history_public_to_subscribers, value)
+        }
+        "stream_post_policy" => stream.stream_post_policy = value.as_i64().map(|v| v as i32),
+        "message_retention_days" => {
+            stream.message_retention_days = value.as_i64().map(|v| v as i32)
+        }
+        _ => {}
+    }
+}
+
+fn update_subscribed_stream(
+    sub: &mut Subscription,
+    property: &str,
+    value: &Value,
+    rendered: &Option<String>,
+) {
+    match property {
+        "name" => set_string(&mut sub.name, value),
+        "description" => {
+            set_string(&mut sub.description, value);
+            if let Some(rendered) = rendered {
+                sub.rendered_description = rendered.clone();
+            }
+        }
+        "invite_only" => set_bool(&mut sub.invite_only, value),
+        "is_web_public" => set_bool(&mut sub.is_web_public, value),
+        "history_public_to_subscribers" => set_bool(&mut sub.history_public_to_subscribers, value),
+        "stream_post_policy" => sub.stream_post_policy = value.as_i64().map(|v| v as i32),
+        "message_retention_days" => sub.message_retention_days = value.as_i64().map(|v| v as i32),
+        _ => {}
+    }
+}
+
+fn update_subscription(sub: &mut Subscription, property: &SubscriptionProperty) {
+    match property {
+        SubscriptionProperty::Color(color) => sub.color = color.clone(),
+        SubscriptionProperty::PinToTop(value) => sub.pin_to_top = *value,
+        SubscriptionProperty::IsMuted(value) => sub.is_muted = *value,
+        SubscriptionProperty::InHomeView(value) => sub.is_muted = !*value,
+        SubscriptionProperty::DesktopNotifications(value) => sub.desktop_notifications = *value,
+        SubscriptionProperty::EmailNotifications(value) => sub.email_notifications = *value,
+        SubscriptionProperty::PushNotifications(value) => sub.push_notifications = *value,
+        SubscriptionProperty::AudibleNotifications(value) => sub.audible_notifications = *value,
+        SubscriptionProperty::WildcardMentionsNotify(value) => {
+            sub.wildcard_mentions_notify = *value
+        }
+        _ => {}
+    }
+}
+
+fn set_string(field: &mut String, value: &Value) {
+    if let Some(value) = value.as_str() {
+        *field = value.to_owned();
+    }
+}
+
+fn set_bool(field: &mut bool, value: &Value) {
+    if let Some(value) = value.as_bool() {
+        *field = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: Value) -> Event {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn initial_state() -> InitialState {
+        serde_json::from_value(json!({
+            "realm_users": [{ "user_id": 10, "email": "iago@zulip.com", "full_name": "Iago" }],
+            "streams": [{ "stream_id": 1, "name": "Denmark" }],
+            "subscriptions": [{ "stream_id": 1, "name": "Denmark", "subscribers": [10] }],
+            "realm_user_groups": [{ "id": 5, "name": "hamletcharacters", "members": [10] }],
+            "realm_name": "Zulip Dev"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn apply_events() {
+        let mut state = RealmState::from_initial_state(&initial_state());
+        let before = state.snapshot();
+
+        state.apply(&event(json!({
+            "id": 0, "type": "realm_user", "op": "update",
+            "person": { "user_id": 10, "full_name": "Iago Z", "role": 200 }
+        })));
+        state.apply(&event(json!({
+            "id": 1, "type": "stream", "op": "update",
+            "stream_id": 1, "name": "Denmark", "property": "name", "value": "Sweden"
+        })));
+        state.apply(&event(json!({
+            "id": 2, "type": "subscription", "op": "peer_add",
+            "stream_ids": [1], "user_ids": [11, 10]
+        })));
+        state.apply(&event(json!({
+            "id": 3, "type": "user_group", "op": "remove_members",
+            "group_id": 5, "user_ids": [10]
+        })));
+        state.apply(&event(json!({
+            "id": 4, "type": "realm", "op": "update_dict", "property": "default",
+            "data": { "name": "Zulip" }
+        })));
+
+        // Snapshots are not affected by later events
+        assert_eq!(before.user(10).unwrap().full_name, "Iago");
+        assert_eq!(before.setting("realm_name"), Some(&json!("Zulip Dev")));
+
+        let after = state.snapshot();
+        let user = after.user_by_email("iago@zulip.com").unwrap();
+        assert_eq!(user.full_name, "Iago Z");
+        assert!(user.is_admin);
+        assert_eq!(after.stream(1).unwrap().name, "Sweden");
+        assert_eq!(after.subscription(1).unwrap().name, "Sweden");
+        assert_eq!(
+            after.subscription(1).unwrap().subscribers,
+            Some(vec![10, 11])
+        );
+        assert!(after.user_group(5).unwrap().members.is_empty());
+        assert_eq!(after.setting("realm_name"), Some(&json!("Zulip")));
+
+        state.apply(&event(json!({
+            "id": 5, "type": "stream", "op": "delete",
+            "streams": [{ "stream_id": 1, "name": "Sweden" }]
+        })));
+        let after = state.snapshot();
+        assert!(after.stream_by_name("Sweden").is_none());
+        assert!(after.subscription(1).is_none());
+
+        state.apply(&event(json!({
+            "id": 6, "type": "realm_user", "op": "remove",
+            "person": { "user_id": 10, "full_name": "Iago Z" }
+        })));
+        let after = state.snapshot();
+        assert!(!after.user(10).unwrap().is_active);
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize, Serializer};
+
+mod manage;
+pub(crate) mod subscriptions;
+pub(crate) mod user_topics;
+
+pub(crate) use manage::{archive, stream, stream_id, topics};
+pub use manage::{CreateStreamBuilder, GetStreamsBuilder, UpdateStreamBuilder};
+pub use subscriptions::{
+    Principals, SubscribeBuilder, SubscribeResponse, SubscriptionProperty, UnsubscribeBuilder,
+    UnsubscribeResponse,
+};
+pub use user_topics::{UserTopic, VisibilityPolicy};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Stream {
+    pub stream_id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rendered_description: String,
+    #[serde(default)]
+    pub date_created: i64,
+    #[serde(default)]
+    pub invite_only: bool,
+    #[serde(default)]
+    pub is_web_public: bool,
+    #[serde(default)]
+    pub history_public_to_subscribers: bool,
+    pub stream_post_policy: Option<i32>,
+    pub message_retention_days: Option<i32>,
+    pub first_message_id: Option<u64>,
+    pub stream_weekly_traffic: Option<i32>,
+}
+
+impl Stream {
+    /// Typed [`stream_post_policy`](Stream::stream_post_policy), if known.
+    pub fn post_policy(&self) -> Option<StreamPostPolicy> {
+        self.stream_post_policy
+            .and_then(|p| StreamPostPolicy::try_from(p).ok())
+    }
+}
+
+/// Who can post to a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StreamPostPolicy {
+    Everyone = 1,
+    Admins = 2,
+    /// Members who are not new, according to the waiting period of the realm.
+    FullMembers = 3,
+    Moderators = 4,
+}
+
+impl TryFrom<i32> for StreamPostPolicy {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Everyone),
+            2 => Ok(Self::Admins),
+            3 => Ok(Self::FullMembers),
+            4 => Ok(Self::Moderators),
+            value => Err(value),
+        }
+    }
+}
+
+impl Serialize for StreamPostPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+/// Topic of a stream, with its latest message.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Topic {
+    pub name: String,
+    pub max_id: u64,
+}
+
+/// A stream as seen by a subscribed user, including the personal settings
+/// of the subscription.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub stream_id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rendered_description: String,
+    #[serde(default)]
+    pub date_created: i64,
+    #[serde(default)]
+    pub invite_only: bool,
+    #[serde(default)]
+    pub is_web_public: bool,
+    #[serde(default)]
+    pub history_public_to_subscribers: bool,
+    pub stream_post_policy: Option<i32>,
+    pub message_retention_days: Option<i32>,
+    pub first_message_id: Option<u64>,
+    pub stream_weekly_traffic: Option<i32>,
+    pub subscribers: Option<Vec<u64>>,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub pin_to_top: bool,
+    #[serde(default)]
+    pub is_muted: bool,
+    pub desktop_notifications: Option<bool>,
+    pub email_notifications: Option<bool>,
+    pub push_notifications: Option<bool>,
+    pub audible_notifications: Option<bool>,
+    pub wildcard_mentions_notify: Option<bool>,
+}
@@ -0,0 +1,346 @@
+use super::subscriptions::{StreamSpec, SubscribeRequest};
+use super::{Stream, StreamPostPolicy, Topic};
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Builder of a stream query, created with
+/// [`Client::streams`](crate::Client::streams). By default, only the public
+/// streams and the ones the user is subscribed to are returned.
+pub struct GetStreamsBuilder {
+    request: GetStreamsRequest,
+    client: Client,
+}
+
+impl GetStreamsBuilder {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            request: GetStreamsRequest::default(),
+            client,
+        }
+    }
+
+    pub fn include_public(mut self, value: bool) -> Self {
+        self.request.include_public.replace(value);
+        self
+    }
+
+    pub fn include_web_public(mut self, value: bool) -> Self {
+        self.request.include_web_public.replace(value);
+        self
+    }
+
+    pub fn include_subscribed(mut self, value: bool) -> Self {
+        self.request.include_subscribed.replace(value);
+        self
+    }
+
+    /// Includes every active stream of the realm. Requires administrator
+    /// privileges.
+    pub fn include_all_active(mut self, value: bool) -> Self {
+        self.request.include_all_active.replace(value);
+        self
+    }
+
+    pub fn include_default(mut self, value: bool) -> Self {
+        self.request.include_default.replace(value);
+        self
+    }
+
+    pub async fn fetch(self) -> Result<Vec<Stream>, Error> {
+        let res: GetStreamsResponse = self
+            .client
+            .send(Method::GET, Endpoint::STREAMS, &self.request)
+            .await?;
+        Ok(res.streams)
+    }
+}
+
+/// Builder of a new stream, created with
+/// [`Client::create_stream`](crate::Client::create_stream).
+///
+/// Streams are created by subscribing the user to them.
+pub struct CreateStreamBuilder {
+    request: SubscribeRequest,
+    client: Client,
+}
+
+impl CreateStreamBuilder {
+    pub(crate) fn new(client: Client, name: String) -> Self {
+        Self {
+            request: SubscribeRequest::new(vec![StreamSpec {
+                name,
+                description: None,
+            }]),
+            client,
+        }
+    }
+
+    pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+        self.request.subscriptions[0]
+            .description
+            .replace(description.into());
+        self
+    }
+
+    pub fn invite_only(mut self, value: bool) -> Self {
+        self.request.invite_only.replace(value);
+        self
+    }
+
+    pub fn web_public(mut self, value: bool) -> Self {
+        self.request.is_web_public.replace(value);
+        self
+    }
+
+    /// Whether new subscribers can read the messages sent before they
+    /// subscribed. Only applies to private streams.
+    pub fn history_public_to_subscribers(mut self, value: bool) -> Self {
+        self.request.history_public_to_subscribers.replace(value);
+        self
+    }
+
+    pub fn post_policy(mut self, policy: StreamPostPolicy) -> Self {
+        self.request.stream_post_policy.replace(policy);
+        self
+    }
+
+    /// Announces the new stream in the notification stream of the realm.
+    pub fn announce(mut self, value: bool) -> Self {
+        self.request.announce.replace(value);
+        self
+    }
+
+    /// Creates the stream, returning its ID. It is looked up by name with a
+    /// second request.
+    ///
+    /// If the stream already exists, the user is subscribed to it instead.
+    pub async fn send(self) -> Result<u64, Error> {
+        let _: EmptyResponse = self
+            .client
+            .send(Method::POST, Endpoint::SUBSCRIPTIONS, &self.request)
+            .await?;
+        stream_id(&self.client, &self.request.subscriptions[0].name).await
+    }
+}
+
+/// Builder of an update of the properties of a stream, created with
+/// [`Client::update_stream`](crate::Client::update_stream).
+pub struct UpdateStreamBuilder {
+    stream_id: u64,
+    request: UpdateStreamRequest,
+    client: Client,
+}
+
+impl UpdateStreamBuilder {
+    pub(crate) fn new(client: Client, stream_id: u64) -> Self {
+        Self {
+            stream_id,
+            request: UpdateStreamRequest::default(),
+            client,
+        }
+    }
+
+    pub fn name<N: Into<String>>(mut self, name: N) -> Self {
+        self.request.new_name.replace(name.into());
+        self
+    }
+
+    pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+        self.request.description.replace(description.into());
+        self
+    }
+
+    pub fn invite_only(mut self, value: bool) -> Self {
+        self.request.is_private.replace(value);
+        self
+    }
+
+    pub fn web_public(mut self, value: bool) -> Self {
+        self.request.is_web_public.replace(value);
+        self
+    }
+
+    pub fn history_public_to_subscribers(mut self, value: bool) -> Self {
+        self.request.history_public_to_subscribers.replace(value);
+        self
+    }
+
+    pub fn post_policy(mut self, policy: StreamPostPolicy) -> Self {
+        self.request.stream_post_policy.replace(policy);
+        self
+    }
+
+    pub async fn send(self) -> Result<(), Error> {
+        let _: EmptyResponse = self
+            .client
+            .send(Method::PATCH, &endpoint(self.stream_id), &self.request)
+            .await?;
+        Ok(())
+    }
+}
+
+pub(crate) async fn stream(client: &Client, stream_id: u64) -> Result<Stream, Error> {
+    let res: GetStreamResponse = client
+        .send(Method::GET, &endpoint(stream_id), &EmptyRequest {})
+        .await?;
+    Ok(res.stream)
+}
+
+pub(crate) async fn stream_id(client: &Client, name: &str) -> Result<u64, Error> {
+    let request = GetStreamIdRequest { stream: name };
+    let res: GetStreamIdResponse = client
+        .send(Method::GET, Endpoint::GET_STREAM_ID, &request)
+        .await?;
+    Ok(res.stream_id)
+}
+
+pub(crate) async fn archive(client: &Client, stream_id: u64) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(Method::DELETE, &endpoint(stream_id), &EmptyRequest {})
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn topics(client: &Client, stream_id: u64) -> Result<Vec<Topic>, Error> {
+    let res: TopicsResponse = client
+        .send(Method::GET, &topics_endpoint(stream_id), &EmptyRequest {})
+        .await?;
+    Ok(res.topics)
+}
+
+fn endpoint(stream_id: u64) -> String {
+    format!("{}/{}", Endpoint::STREAMS, stream_id)
+}
+
+fn topics_endpoint(stream_id: u64) -> String {
+    format!("{}/{}/topics", Endpoint::OWN_USER, stream_id)
+}
+
+#[derive(Serialize, Debug, Default)]
+struct GetStreamsRequest {
+    include_public: Option<bool>,
+    include_web_public: Option<bool>,
+    include_subscribed: Option<bool>,
+    include_all_active: Option<bool>,
+    include_default: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct GetStreamsResponse {
+    streams: Vec<Stream>,
+}
+
+#[derive(Deserialize)]
+struct GetStreamResponse {
+    stream: Stream,
+}
+
+#[derive(Serialize)]
+struct GetStreamIdRequest<'a> {
+    stream: &'a str,
+}
+
+#[derive(Deserialize)]
+struct GetStreamIdResponse {
+    stream_id: u64,
+}
+
+#[derive(Deserialize)]
+struct TopicsResponse {
+    topics: Vec<Topic>,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct UpdateStreamRequest {
+    new_name: Option<String>,
+    description: Option<String>,
+    is_private: Option<bool>,
+    is_web_public: Option<bool>,
+    history_public_to_subscribers: Option<bool>,
+    stream_post_policy: Option<StreamPostPolicy>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    fn success() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }))
+    }
+
+    #[tokio::test]
+    async fn create_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method("POST", success(), Endpoint::SUBSCRIPTIONS).expect(1))
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "stream_id": 15, "msg": "", "result": "success" })),
+                Endpoint::GET_STREAM_ID,
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let stream_id = client
+            .create_stream("Denmark")
+            .description("Castle")
+            .invite_only(true)
+            .post_policy(StreamPostPolicy::Admins)
+            .send()
+            .await?;
+        assert_eq!(stream_id, 15);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(
+            String::from_utf8(requests[0].body.clone())?,
+            "subscriptions=%5B%7B%22name%22%3A%22Denmark%22%2C%22description%22%3A%22Castle%22%7D%5D\
+             &invite_only=true&stream_post_policy=2"
+        );
+        assert_eq!(requests[1].url.query(), Some("stream=Denmark"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_stream() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        server
+            .register(mock_method("PATCH", success(), "streams/15").expect(1))
+            .await;
+        server
+            .register(mock_method(
+                "GET",
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "topics": [{ "max_id": 26, "name": "Castle" }],
+                    "msg": "", "result": "success"
+                })),
+                "users/me/15/topics",
+            ))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .update_stream(15)
+            .name("Sweden")
+            .invite_only(false)
+            .history_public_to_subscribers(true)
+            .send()
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "new_name=Sweden&is_private=false&history_public_to_subscribers=true"
+        );
+
+        let topics = client.stream_topics(15).await?;
+        assert_eq!(topics[0].name, "Castle");
+        assert_eq!(topics[0].max_id, 26);
+        Ok(())
+    }
+}
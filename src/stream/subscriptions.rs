@@ -0,0 +1,327 @@
+use super::Subscription;
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyResponse, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Users to subscribe or unsubscribe, instead of the current user.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Principals {
+    UserIds(Vec<u64>),
+    Emails(Vec<String>),
+}
+
+impl Principals {
+    pub fn user_ids<I: IntoIterator<Item = u64>>(ids: I) -> Self {
+        Self::UserIds(ids.into_iter().collect())
+    }
+
+    pub fn emails<I, E>(emails: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Into<String>,
+    {
+        Self::Emails(emails.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Personal setting of a subscription, with its new value.
+///
+/// Notification settings set to `None` follow the global settings of the
+/// user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "property", content = "value", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SubscriptionProperty {
+    /// Hex color of the stream, e.g. `#f00f00`.
+    Color(String),
+    PinToTop(bool),
+    IsMuted(bool),
+    /// Deprecated inverse of [`IsMuted`](SubscriptionProperty::IsMuted),
+    /// still sent by older servers.
+    InHomeView(bool),
+    DesktopNotifications(Option<bool>),
+    EmailNotifications(Option<bool>),
+    PushNotifications(Option<bool>),
+    AudibleNotifications(Option<bool>),
+    WildcardMentionsNotify(Option<bool>),
+    /// Property not typed by this library, e.g. added by a newer server.
+    #[serde(untagged)]
+    Other {
+        property: String,
+        value: Value,
+    },
+}
+
+/// Builder of a subscription to one or more streams, created with
+/// [`Client::subscribe`](crate::Client::subscribe). Missing streams are
+/// created.
+pub struct SubscribeBuilder {
+    request: SubscribeRequest,
+    client: Client,
+}
+
+impl SubscribeBuilder {
+    pub(crate) fn new(client: Client, streams: Vec<String>) -> Self {
+        let subscriptions = streams
+            .into_iter()
+            .map(|name| StreamSpec {
+                name,
+                description: None,
+            })
+            .collect();
+        Self {
+            request: SubscribeRequest::new(subscriptions),
+            client,
+        }
+    }
+
+    /// Subscribes the given users instead of the current one.
+    pub fn principals(mut self, principals: Principals) -> Self {
+        self.request.principals.replace(principals);
+        self
+    }
+
+    /// Announces the streams created by the request in the notification
+    /// stream of the realm.
+    pub fn announce(mut self, value: bool) -> Self {
+        self.request.announce.replace(value);
+        self
+    }
+
+    /// Whether the streams created by the request are private.
+    pub fn invite_only(mut self, value: bool) -> Self {
+        self.request.invite_only.replace(value);
+        self
+    }
+
+    /// Whether to fail if the user cannot access one of the streams, or to
+    /// subscribe to the others, the default.
+    pub fn authorization_errors_fatal(mut self, value: bool) -> Self {
+        self.request.authorization_errors_fatal.replace(value);
+        self
+    }
+
+    pub async fn send(self) -> Result<SubscribeResponse, Error> {
+        self.client
+            .send(Method::POST, Endpoint::SUBSCRIPTIONS, &self.request)
+            .await
+    }
+}
+
+/// Builder of the removal of subscriptions, created with
+/// [`Client::unsubscribe`](crate::Client::unsubscribe).
+pub struct UnsubscribeBuilder {
+    request: UnsubscribeRequest,
+    client: Client,
+}
+
+impl UnsubscribeBuilder {
+    pub(crate) fn new(client: Client, streams: Vec<String>) -> Self {
+        Self {
+            request: UnsubscribeRequest {
+                subscriptions: streams,
+                principals: None,
+            },
+            client,
+        }
+    }
+
+    /// Unsubscribes the given users instead of the current one.
+    pub fn principals(mut self, principals: Principals) -> Self {
+        self.request.principals.replace(principals);
+        self
+    }
+
+    pub async fn send(self) -> Result<UnsubscribeResponse, Error> {
+        self.client
+            .send(Method::DELETE, Endpoint::SUBSCRIPTIONS, &self.request)
+            .await
+    }
+}
+
+/// Streams to which each user, by email or ID depending on the server
+/// version, was subscribed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubscribeResponse {
+    pub subscribed: HashMap<String, Vec<String>>,
+    pub already_subscribed: HashMap<String, Vec<String>>,
+    /// Streams the user was not allowed to subscribe to.
+    #[serde(default)]
+    pub unauthorized: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UnsubscribeResponse {
+    pub removed: Vec<String>,
+    /// Streams to which the users were not subscribed.
+    pub not_removed: Vec<String>,
+}
+
+pub(crate) async fn list(
+    client: &Client,
+    include_subscribers: bool,
+) -> Result<Vec<Subscription>, Error> {
+    let request = ListSubscriptionsRequest {
+        include_subscribers,
+    };
+    let res: ListSubscriptionsResponse = client
+        .send(Method::GET, Endpoint::SUBSCRIPTIONS, &request)
+        .await?;
+    Ok(res.subscriptions)
+}
+
+pub(crate) async fn update<I>(client: &Client, changes: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = (u64, SubscriptionProperty)>,
+{
+    let subscription_data = changes
+        .into_iter()
+        .map(|(stream_id, property)| SubscriptionChange {
+            stream_id,
+            property,
+        })
+        .collect();
+    let request = UpdateSubscriptionsRequest { subscription_data };
+    let _: EmptyResponse = client
+        .send(Method::POST, Endpoint::SUBSCRIPTION_PROPERTIES, &request)
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+pub(super) struct SubscribeRequest {
+    #[serde(serialize_with = "crate::serialize")]
+    pub(super) subscriptions: Vec<StreamSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::serialize")]
+    pub(super) principals: Option<Principals>,
+    pub(super) authorization_errors_fatal: Option<bool>,
+    pub(super) announce: Option<bool>,
+    pub(super) invite_only: Option<bool>,
+    pub(super) is_web_public: Option<bool>,
+    pub(super) history_public_to_subscribers: Option<bool>,
+    pub(super) stream_post_policy: Option<super::StreamPostPolicy>,
+}
+
+impl SubscribeRequest {
+    pub(super) fn new(subscriptions: Vec<StreamSpec>) -> Self {
+        Self {
+            subscriptions,
+            principals: None,
+            authorization_errors_fatal: None,
+            announce: None,
+            invite_only: None,
+            is_web_public: None,
+            history_public_to_subscribers: None,
+            stream_post_policy: None,
+        }
+    }
+}
+
+/// Stream to subscribe to, with the description to use if it is created.
+#[derive(Serialize, Debug)]
+pub(super) struct StreamSpec {
+    pub(super) name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) description: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct UnsubscribeRequest {
+    #[serde(serialize_with = "crate::serialize")]
+    subscriptions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::serialize")]
+    principals: Option<Principals>,
+}
+
+#[derive(Serialize, Debug)]
+struct ListSubscriptionsRequest {
+    include_subscribers: bool,
+}
+
+#[derive(Deserialize)]
+struct ListSubscriptionsResponse {
+    subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Debug)]
+struct UpdateSubscriptionsRequest {
+    #[serde(serialize_with = "crate::serialize")]
+    subscription_data: Vec<SubscriptionChange>,
+}
+
+#[derive(Serialize, Debug)]
+struct SubscriptionChange {
+    stream_id: u64,
+    #[serde(flatten)]
+    property: SubscriptionProperty,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn subscribe() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template = ResponseTemplate::new(200).set_body_json(json!({
+            "subscribed": { "10": ["Denmark"] },
+            "already_subscribed": { "11": ["Denmark"] },
+            "msg": "", "result": "success"
+        }));
+        server
+            .register(mock_method("POST", template, Endpoint::SUBSCRIPTIONS).expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let res = client
+            .subscribe(["Denmark"])
+            .principals(Principals::user_ids([10, 11]))
+            .announce(true)
+            .send()
+            .await?;
+        assert_eq!(res.subscribed["10"], vec!["Denmark".to_owned()]);
+        assert!(res.unauthorized.is_empty());
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "subscriptions=%5B%7B%22name%22%3A%22Denmark%22%7D%5D\
+             &principals=%5B10%2C11%5D&announce=true"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_subscriptions() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template = ResponseTemplate::new(200).set_body_json(json!({
+            "subscription_data": [], "msg": "", "result": "success"
+        }));
+        server
+            .register(mock_method("POST", template, Endpoint::SUBSCRIPTION_PROPERTIES).expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .update_subscriptions([
+                (1, SubscriptionProperty::PinToTop(true)),
+                (2, SubscriptionProperty::PushNotifications(None)),
+            ])
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "subscription_data=%5B\
+             %7B%22stream_id%22%3A1%2C%22property%22%3A%22pin_to_top%22%2C%22value%22%3Atrue%7D%2C\
+             %7B%22stream_id%22%3A2%2C%22property%22%3A%22push_notifications%22%2C%22value%22%3Anull%7D\
+             %5D"
+        );
+        Ok(())
+    }
+}
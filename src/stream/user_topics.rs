@@ -0,0 +1,127 @@
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyResponse, Error};
+use reqwest::Method;
+use serde::de::Deserializer;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// How the user is notified of the messages of a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VisibilityPolicy {
+    /// Follows the settings of the stream.
+    Inherit,
+    Muted,
+    /// Unmuted in a muted stream.
+    Unmuted,
+    Followed,
+    /// Policy added by a newer server, with its raw value.
+    Unknown(i32),
+}
+
+impl From<i32> for VisibilityPolicy {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Inherit,
+            1 => Self::Muted,
+            2 => Self::Unmuted,
+            3 => Self::Followed,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<VisibilityPolicy> for i32 {
+    fn from(value: VisibilityPolicy) -> Self {
+        match value {
+            VisibilityPolicy::Inherit => 0,
+            VisibilityPolicy::Muted => 1,
+            VisibilityPolicy::Unmuted => 2,
+            VisibilityPolicy::Followed => 3,
+            VisibilityPolicy::Unknown(value) => value,
+        }
+    }
+}
+
+impl Serialize for VisibilityPolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32((*self).into())
+    }
+}
+
+impl<'de> Deserialize<'de> for VisibilityPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i32::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Visibility policy of a topic set by the user, as sent in the initial
+/// state and in `user_topic` events.
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserTopic {
+    pub stream_id: u64,
+    pub topic_name: String,
+    pub last_updated: i64,
+    pub visibility_policy: VisibilityPolicy,
+}
+
+pub(crate) async fn set_visibility(
+    client: &Client,
+    stream_id: u64,
+    topic: String,
+    visibility_policy: VisibilityPolicy,
+) -> Result<(), Error> {
+    let request = UserTopicRequest {
+        stream_id,
+        topic,
+        visibility_policy,
+    };
+    let _: EmptyResponse = client
+        .send(Method::POST, Endpoint::USER_TOPICS, &request)
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Debug)]
+struct UserTopicRequest {
+    stream_id: u64,
+    topic: String,
+    visibility_policy: VisibilityPolicy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_server};
+    use serde_json::json;
+    use wiremock::ResponseTemplate;
+
+    #[tokio::test]
+    async fn set_topic_visibility() -> Result<(), Box<dyn std::error::Error>> {
+        let template =
+            ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }));
+        let server = mock_server(template, Endpoint::USER_TOPICS).await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .set_topic_visibility(1, "Incident 42", VisibilityPolicy::Followed)
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "stream_id=1&topic=Incident+42&visibility_policy=3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_visibility_policy() -> Result<(), serde_json::Error> {
+        let topic: UserTopic = serde_json::from_value(json!({
+            "stream_id": 1,
+            "topic_name": "Incident 42",
+            "last_updated": 1700000000,
+            "visibility_policy": 7
+        }))?;
+        assert_eq!(topic.visibility_policy, VisibilityPolicy::Unknown(7));
+        assert_eq!(serde_json::to_value(topic.visibility_policy)?, json!(7));
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::endpoint::Endpoint;
 use serde::Serialize;
+use serde_json::Value;
 use std::string::FromUtf8Error;
 use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
 
@@ -28,6 +29,63 @@ impl MockAuthResponse {
     }
 }
 
+#[derive(Serialize)]
+pub struct MockRegisterResponse {
+    queue_id: String,
+    last_event_id: i32,
+    zulip_version: String,
+    zulip_feature_level: u16,
+    zulip_merge_base: String,
+    #[serde(flatten)]
+    state: serde_json::Map<String, Value>,
+    result: String,
+}
+
+impl MockRegisterResponse {
+    pub const QUEUE_ID: &'static str = "1518820930:1";
+    pub const ZULIP_VERSION: &'static str = "8.0";
+    pub const ZULIP_FEATURE_LEVEL: u16 = 237;
+
+    pub fn new() -> Self {
+        Self {
+            queue_id: Self::QUEUE_ID.to_owned(),
+            last_event_id: -1,
+            zulip_version: Self::ZULIP_VERSION.to_owned(),
+            zulip_feature_level: Self::ZULIP_FEATURE_LEVEL,
+            zulip_merge_base: Self::ZULIP_VERSION.to_owned(),
+            state: serde_json::Map::new(),
+            result: "success".to_owned(),
+        }
+    }
+
+    /// Adds initial state to the response
+    pub fn with_state(mut self, state: Value) -> Self {
+        if let Value::Object(state) = state {
+            self.state.extend(state);
+        }
+        self
+    }
+}
+
+#[derive(Serialize)]
+pub struct MockEventsResponse {
+    events: Vec<Value>,
+    result: String,
+}
+
+impl MockEventsResponse {
+    pub fn new(events: Vec<Value>) -> Self {
+        Self {
+            events,
+            result: "success".to_owned(),
+        }
+    }
+
+    pub fn heartbeat(id: i32) -> Self {
+        Self::new(vec![serde_json::json!({ "id": id, "type": "heartbeat" })])
+    }
+}
+
 #[derive(Serialize)]
 pub struct MockErrorResponse {
     msg: String,
@@ -35,6 +93,7 @@ pub struct MockErrorResponse {
     var_name: Option<String>,
     retry_after: Option<f32>,
     queue_id: Option<String>,
+    stream: Option<String>,
 }
 
 impl MockErrorResponse {
@@ -45,6 +104,7 @@ impl MockErrorResponse {
             var_name: None,
             retry_after: None,
             queue_id: None,
+            stream: None,
         }
     }
 
@@ -92,17 +152,34 @@ impl MockErrorResponse {
         res.queue_id = Some("1518820930:1".to_owned());
         res
     }
+
+    pub fn stream_not_found() -> Self {
+        let mut res = Self::new("Stream 'Nowhere' does not exist");
+        res.code = Some("STREAM_DOES_NOT_EXIST".to_owned());
+        res.stream = Some("Nowhere".to_owned());
+        res
+    }
+
+    pub fn wildcard_mention_not_allowed() -> Self {
+        let mut res =
+            Self::new("You do not have permission to use wildcard mentions in this stream.");
+        res.code = Some("STREAM_WILDCARD_MENTION_NOT_ALLOWED".to_owned());
+        res
+    }
 }
 
 pub fn mock(response: ResponseTemplate, endpoint: &str) -> Mock {
-    Mock::given(matchers::method("POST"))
+    mock_method("POST", response, endpoint).expect(1)
+}
+
+pub fn mock_method(method: &str, response: ResponseTemplate, endpoint: &str) -> Mock {
+    Mock::given(matchers::method(method))
         .and(matchers::path(format!(
             "{}{}",
             Endpoint::BASE_API,
             endpoint
         )))
         .respond_with(response)
-        .expect(1)
 }
 
 pub async fn mock_server(response: ResponseTemplate, endpoint: &str) -> MockServer {
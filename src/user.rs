@@ -0,0 +1,119 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+
+mod manage;
+pub(crate) mod settings;
+
+pub(crate) use manage::{create, deactivate, reactivate, user, user_by_email};
+pub use manage::{GetUsersBuilder, UpdateUserBuilder};
+pub use settings::{ColorScheme, EmailAddressVisibility, UserSetting, UserSettings};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct User {
+    pub user_id: u64,
+    pub email: String,
+    pub delivery_email: Option<String>,
+    pub full_name: String,
+    #[serde(default)]
+    pub date_joined: String,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub is_owner: bool,
+    #[serde(default)]
+    pub is_admin: bool,
+    #[serde(default)]
+    pub is_guest: bool,
+    #[serde(default)]
+    pub is_billing_admin: bool,
+    /// Missing for servers older than Zulip 4.0, or unknown to this library.
+    #[serde(default, deserialize_with = "known_role")]
+    pub role: Option<UserRole>,
+    #[serde(default)]
+    pub is_bot: bool,
+    pub bot_type: Option<i32>,
+    pub bot_owner_id: Option<u64>,
+    pub avatar_url: Option<String>,
+    pub avatar_version: Option<i32>,
+    #[serde(default)]
+    pub timezone: String,
+    #[serde(default)]
+    pub profile_data: HashMap<String, ProfileData>,
+}
+
+/// Role of a user in the realm, from the most to the least privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum UserRole {
+    Owner = 100,
+    Admin = 200,
+    Moderator = 300,
+    Member = 400,
+    Guest = 600,
+}
+
+impl UserRole {
+    pub fn is_admin(&self) -> bool {
+        matches!(self, Self::Owner | Self::Admin)
+    }
+}
+
+impl TryFrom<i32> for UserRole {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            100 => Ok(Self::Owner),
+            200 => Ok(Self::Admin),
+            300 => Ok(Self::Moderator),
+            400 => Ok(Self::Member),
+            600 => Ok(Self::Guest),
+            value => Err(value),
+        }
+    }
+}
+
+impl Serialize for UserRole {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+impl<'de> Deserialize<'de> for UserRole {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = i32::deserialize(deserializer)?;
+        Self::try_from(value).map_err(|v| de::Error::custom(format!("unknown role {}", v)))
+    }
+}
+
+/// Roles added by newer servers are ignored rather than failing the whole
+/// user.
+fn known_role<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<UserRole>, D::Error> {
+    let value = Option::<i32>::deserialize(deserializer)?;
+    Ok(value.and_then(|v| UserRole::try_from(v).ok()))
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ProfileData {
+    pub value: String,
+    pub rendered_value: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserGroup {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub members: Vec<u64>,
+    #[serde(default)]
+    pub direct_subgroup_ids: Vec<u64>,
+    #[serde(default)]
+    pub is_system_group: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,263 @@
+use super::{User, UserRole};
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// Characters escaped in a path segment, keeping those common in emails.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'@')
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Builder of a query of all the users of the realm, created with
+/// [`Client::users`](crate::Client::users).
+pub struct GetUsersBuilder {
+    request: GetUsersRequest,
+    client: Client,
+}
+
+impl GetUsersBuilder {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            request: GetUsersRequest::default(),
+            client,
+        }
+    }
+
+    /// Omits the avatar URL of users using Gravatar, to be computed by the
+    /// client from their email.
+    pub fn client_gravatar(mut self, value: bool) -> Self {
+        self.request.client_gravatar.replace(value);
+        self
+    }
+
+    pub fn include_custom_profile_fields(mut self, value: bool) -> Self {
+        self.request.include_custom_profile_fields.replace(value);
+        self
+    }
+
+    pub async fn fetch(self) -> Result<Vec<User>, Error> {
+        let res: GetUsersResponse = self
+            .client
+            .send(Method::GET, Endpoint::USERS, &self.request)
+            .await?;
+        Ok(res.members)
+    }
+}
+
+/// Builder of an update of a user by an administrator, created with
+/// [`Client::update_user`](crate::Client::update_user).
+pub struct UpdateUserBuilder {
+    user_id: u64,
+    request: UpdateUserRequest,
+    client: Client,
+}
+
+impl UpdateUserBuilder {
+    pub(crate) fn new(client: Client, user_id: u64) -> Self {
+        Self {
+            user_id,
+            request: UpdateUserRequest::default(),
+            client,
+        }
+    }
+
+    pub fn full_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.request.full_name.replace(name.into());
+        self
+    }
+
+    /// Only owners can grant or revoke the owner role.
+    pub fn role(mut self, role: UserRole) -> Self {
+        self.request.role.replace(role);
+        self
+    }
+
+    /// Sets the value of a custom profile field, by field ID. An empty value
+    /// clears the field.
+    pub fn profile_field<V: Into<String>>(mut self, field_id: u64, value: V) -> Self {
+        self.request
+            .profile_data
+            .get_or_insert_with(Vec::new)
+            .push(ProfileFieldValue {
+                id: field_id,
+                value: value.into(),
+            });
+        self
+    }
+
+    pub async fn send(self) -> Result<(), Error> {
+        let _: EmptyResponse = self
+            .client
+            .send(Method::PATCH, &endpoint(self.user_id), &self.request)
+            .await?;
+        Ok(())
+    }
+}
+
+pub(crate) async fn user(client: &Client, user_id: u64) -> Result<User, Error> {
+    user_at(client, &endpoint(user_id)).await
+}
+
+pub(crate) async fn user_by_email(client: &Client, email: &str) -> Result<User, Error> {
+    let email = utf8_percent_encode(email, PATH_SEGMENT);
+    user_at(client, &format!("{}/{}", Endpoint::USERS, email)).await
+}
+
+async fn user_at(client: &Client, endpoint: &str) -> Result<User, Error> {
+    let res: GetUserResponse = client.send(Method::GET, endpoint, &EmptyRequest {}).await?;
+    Ok(res.user)
+}
+
+pub(crate) async fn create(
+    client: &Client,
+    email: String,
+    password: String,
+    full_name: String,
+) -> Result<u64, Error> {
+    let request = CreateUserRequest {
+        email,
+        password,
+        full_name,
+    };
+    let res: CreateUserResponse = client.send(Method::POST, Endpoint::USERS, &request).await?;
+    Ok(res.user_id)
+}
+
+pub(crate) async fn deactivate(client: &Client, user_id: u64) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(Method::DELETE, &endpoint(user_id), &EmptyRequest {})
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn reactivate(client: &Client, user_id: u64) -> Result<(), Error> {
+    let endpoint = format!("{}/reactivate", endpoint(user_id));
+    let _: EmptyResponse = client
+        .send(Method::POST, &endpoint, &EmptyRequest {})
+        .await?;
+    Ok(())
+}
+
+fn endpoint(user_id: u64) -> String {
+    format!("{}/{}", Endpoint::USERS, user_id)
+}
+
+#[derive(Serialize, Debug, Default)]
+struct GetUsersRequest {
+    client_gravatar: Option<bool>,
+    include_custom_profile_fields: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct GetUsersResponse {
+    members: Vec<User>,
+}
+
+#[derive(Deserialize)]
+struct GetUserResponse {
+    user: User,
+}
+
+#[derive(Serialize, Debug)]
+struct CreateUserRequest {
+    email: String,
+    password: String,
+    full_name: String,
+}
+
+#[derive(Deserialize)]
+struct CreateUserResponse {
+    user_id: u64,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct UpdateUserRequest {
+    full_name: Option<String>,
+    role: Option<UserRole>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "crate::serialize")]
+    profile_data: Option<Vec<ProfileFieldValue>>,
+}
+
+#[derive(Serialize, Debug)]
+struct ProfileFieldValue {
+    id: u64,
+    value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn get_user() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template = ResponseTemplate::new(200).set_body_json(json!({
+            "user": {
+                "user_id": 10, "email": "iago@zulip.com", "full_name": "Iago",
+                "is_admin": true, "role": 200
+            },
+            "msg": "", "result": "success"
+        }));
+        server
+            .register(mock_method("GET", template, "users/iago@zulip.com").expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let user = client.user_by_email("iago@zulip.com").await?;
+        assert_eq!(user.user_id, 10);
+        assert_eq!(user.role, Some(UserRole::Admin));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_user_by_special_email() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template = ResponseTemplate::new(200).set_body_json(json!({
+            "user": {
+                "user_id": 11, "email": "a%b?c#d@zulip.com", "full_name": "Abcd",
+                "is_admin": false, "role": 400
+            },
+            "msg": "", "result": "success"
+        }));
+        server
+            .register(mock_method("GET", template, "users/a%25b%3Fc%23d@zulip.com").expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        let user = client.user_by_email("a%b?c#d@zulip.com").await?;
+        assert_eq!(user.user_id, 11);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_user() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template =
+            ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }));
+        server
+            .register(mock_method("PATCH", template, "users/10").expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .update_user(10)
+            .role(UserRole::Moderator)
+            .profile_field(4, "Sales")
+            .send()
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "role=300&profile_data=%5B%7B%22id%22%3A4%2C%22value%22%3A%22Sales%22%7D%5D"
+        );
+        Ok(())
+    }
+}
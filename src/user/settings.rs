@@ -0,0 +1,288 @@
+use super::User;
+use crate::endpoint::Endpoint;
+use crate::{Client, EmptyRequest, EmptyResponse, Error};
+use reqwest::Method;
+use serde::de::{self, Deserializer};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ColorScheme {
+    /// Follows the system settings.
+    Automatic = 1,
+    Dark = 2,
+    Light = 3,
+}
+
+impl TryFrom<i32> for ColorScheme {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Automatic),
+            2 => Ok(Self::Dark),
+            3 => Ok(Self::Light),
+            value => Err(value),
+        }
+    }
+}
+
+impl Serialize for ColorScheme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorScheme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = i32::deserialize(deserializer)?;
+        Self::try_from(value).map_err(|v| de::Error::custom(format!("unknown color scheme {}", v)))
+    }
+}
+
+/// Who can see the real email address of the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmailAddressVisibility {
+    Everyone = 1,
+    Members = 2,
+    Admins = 3,
+    Nobody = 4,
+    Moderators = 5,
+}
+
+impl TryFrom<i32> for EmailAddressVisibility {
+    type Error = i32;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Everyone),
+            2 => Ok(Self::Members),
+            3 => Ok(Self::Admins),
+            4 => Ok(Self::Nobody),
+            5 => Ok(Self::Moderators),
+            value => Err(value),
+        }
+    }
+}
+
+impl Serialize for EmailAddressVisibility {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
+impl<'de> Deserialize<'de> for EmailAddressVisibility {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = i32::deserialize(deserializer)?;
+        Self::try_from(value)
+            .map_err(|v| de::Error::custom(format!("unknown email address visibility {}", v)))
+    }
+}
+
+/// Single setting of the current user, with its value.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "property", content = "value", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UserSetting {
+    /// IANA name of the timezone, e.g. `Europe/Paris`.
+    Timezone(String),
+    DefaultLanguage(String),
+    TwentyFourHourTime(bool),
+    ColorScheme(ColorScheme),
+    Emojiset(String),
+    LeftSideUserlist(bool),
+    EnableDesktopNotifications(bool),
+    EnableSounds(bool),
+    EnableOfflineEmailNotifications(bool),
+    EnableOfflinePushNotifications(bool),
+    EnableOnlinePushNotifications(bool),
+    EnableStreamDesktopNotifications(bool),
+    EnableStreamEmailNotifications(bool),
+    EnableStreamPushNotifications(bool),
+    EnableStreamAudibleNotifications(bool),
+    WildcardMentionsNotify(bool),
+    EmailAddressVisibility(EmailAddressVisibility),
+    /// Setting not typed by this library, or a value unknown to it, e.g.
+    /// added by a newer server.
+    #[serde(untagged)]
+    Other {
+        property: String,
+        value: Value,
+    },
+}
+
+/// Settings of the current user, as sent in the initial state of a queue.
+///
+/// Only the settings known by this library are kept. Values unknown to it
+/// are `None`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct UserSettings {
+    pub timezone: Option<String>,
+    pub default_language: Option<String>,
+    pub twenty_four_hour_time: Option<bool>,
+    #[serde(default, deserialize_with = "known")]
+    pub color_scheme: Option<ColorScheme>,
+    pub emojiset: Option<String>,
+    pub left_side_userlist: Option<bool>,
+    pub enable_desktop_notifications: Option<bool>,
+    pub enable_sounds: Option<bool>,
+    pub enable_offline_email_notifications: Option<bool>,
+    pub enable_offline_push_notifications: Option<bool>,
+    pub enable_online_push_notifications: Option<bool>,
+    pub enable_stream_desktop_notifications: Option<bool>,
+    pub enable_stream_email_notifications: Option<bool>,
+    pub enable_stream_push_notifications: Option<bool>,
+    pub enable_stream_audible_notifications: Option<bool>,
+    pub wildcard_mentions_notify: Option<bool>,
+    #[serde(default, deserialize_with = "known")]
+    pub email_address_visibility: Option<EmailAddressVisibility>,
+}
+
+impl UserSettings {
+    /// Applies the setting of a `user_settings` event.
+    pub fn apply(&mut self, setting: &UserSetting) {
+        match setting.clone() {
+            UserSetting::Timezone(v) => self.timezone = Some(v),
+            UserSetting::DefaultLanguage(v) => self.default_language = Some(v),
+            UserSetting::TwentyFourHourTime(v) => self.twenty_four_hour_time = Some(v),
+            UserSetting::ColorScheme(v) => self.color_scheme = Some(v),
+            UserSetting::Emojiset(v) => self.emojiset = Some(v),
+            UserSetting::LeftSideUserlist(v) => self.left_side_userlist = Some(v),
+            UserSetting::EnableDesktopNotifications(v) => {
+                self.enable_desktop_notifications = Some(v)
+            }
+            UserSetting::EnableSounds(v) => self.enable_sounds = Some(v),
+            UserSetting::EnableOfflineEmailNotifications(v) => {
+                self.enable_offline_email_notifications = Some(v)
+            }
+            UserSetting::EnableOfflinePushNotifications(v) => {
+                self.enable_offline_push_notifications = Some(v)
+            }
+            UserSetting::EnableOnlinePushNotifications(v) => {
+                self.enable_online_push_notifications = Some(v)
+            }
+            UserSetting::EnableStreamDesktopNotifications(v) => {
+                self.enable_stream_desktop_notifications = Some(v)
+            }
+            UserSetting::EnableStreamEmailNotifications(v) => {
+                self.enable_stream_email_notifications = Some(v)
+            }
+            UserSetting::EnableStreamPushNotifications(v) => {
+                self.enable_stream_push_notifications = Some(v)
+            }
+            UserSetting::EnableStreamAudibleNotifications(v) => {
+                self.enable_stream_audible_notifications = Some(v)
+            }
+            UserSetting::WildcardMentionsNotify(v) => self.wildcard_mentions_notify = Some(v),
+            UserSetting::EmailAddressVisibility(v) => self.email_address_visibility = Some(v),
+            // Unknown values are `None`, as in the initial state
+            UserSetting::Other { property, .. } => match property.as_str() {
+                "color_scheme" => self.color_scheme = None,
+                "email_address_visibility" => self.email_address_visibility = None,
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Values added by newer servers are ignored rather than failing the whole
+/// settings.
+fn known<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<i32>,
+{
+    let value = Option::<i32>::deserialize(deserializer)?;
+    Ok(value.and_then(|v| T::try_from(v).ok()))
+}
+
+pub(crate) async fn me(client: &Client) -> Result<User, Error> {
+    client
+        .send(Method::GET, Endpoint::OWN_USER, &EmptyRequest {})
+        .await
+}
+
+pub(crate) async fn update(client: &Client, settings: Vec<UserSetting>) -> Result<(), Error> {
+    let _: EmptyResponse = client
+        .send(
+            Method::PATCH,
+            Endpoint::SETTINGS,
+            &UpdateSettingsRequest(settings),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Settings sent as one parameter each, named after the property.
+#[derive(Debug)]
+struct UpdateSettingsRequest(Vec<UserSetting>);
+
+impl Serialize for UpdateSettingsRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for setting in &self.0 {
+            let entry = serde_json::to_value(setting).map_err(ser::Error::custom)?;
+            match (entry.get("property"), entry.get("value")) {
+                (Some(Value::String(property)), Some(value)) => {
+                    map.serialize_entry(property, value)?
+                }
+                _ => return Err(ser::Error::custom("Invalid user setting")),
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{body_as_string, mock_method};
+    use serde_json::json;
+    use wiremock::{MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn update_settings() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let template =
+            ResponseTemplate::new(200).set_body_json(json!({ "msg": "", "result": "success" }));
+        server
+            .register(mock_method("PATCH", template, Endpoint::SETTINGS).expect(1))
+            .await;
+        let client = Client::build(server.uri()).init().await?;
+
+        client
+            .update_settings([
+                UserSetting::Timezone("Europe/Paris".into()),
+                UserSetting::ColorScheme(ColorScheme::Dark),
+                UserSetting::EnableSounds(false),
+                UserSetting::EmailAddressVisibility(EmailAddressVisibility::Admins),
+            ])
+            .await?;
+        assert_eq!(
+            body_as_string(&server).await?.unwrap(),
+            "timezone=Europe%2FParis&color_scheme=2&enable_sounds=false&email_address_visibility=3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn apply_setting() {
+        let mut settings: UserSettings =
+            serde_json::from_value(json!({ "color_scheme": 9, "enable_sounds": true })).unwrap();
+        assert_eq!(settings.color_scheme, None);
+
+        settings.apply(&UserSetting::ColorScheme(ColorScheme::Light));
+        settings.apply(&UserSetting::EnableSounds(false));
+        assert_eq!(settings.color_scheme, Some(ColorScheme::Light));
+        assert_eq!(settings.enable_sounds, Some(false));
+
+        settings.apply(&UserSetting::Other {
+            property: "color_scheme".into(),
+            value: json!(4),
+        });
+        assert_eq!(settings.color_scheme, None);
+    }
+}